@@ -0,0 +1,145 @@
+use crate::vec3::{Vec3};
+use crate::types::*;
+use crate::camera::ProjectiveCamera;
+
+use std::ops::{Add, Sub, Mul};
+
+// --------------------------------------------------------------------------------------------------------------------
+// Keyframe interpolation. Linear goes straight from key to key, spline goes through the keys on a smooth
+// Catmull-Rom curve (the first and last key are repeated at the ends).
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Interpolation {
+    Linear,
+    Spline
+}
+
+impl Default for Interpolation {
+    fn default() -> Self { Interpolation::Linear }
+}
+
+impl Interpolation {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "linear" => { Some(Interpolation::Linear) }
+            "spline" => { Some(Interpolation::Spline) }
+            _ => { Option::None }
+        }
+    }
+}
+
+// Position in the keys sorted by time: index of the key at or before time, and how far it is to the next one.
+// Before the first key and after the last one the value holds still.
+pub fn locate_key(times: &[Float], time: Float) -> (usize, Float) {
+    let last = times.len() - 1;
+    if time <= times[0] {
+        return (0, 0.0);
+    }
+    if time >= times[last] {
+        return (last, 0.0);
+    }
+
+    let i = times.partition_point(|&t| t <= time) - 1;
+    let span = times[i + 1] - times[i];
+
+    (i, if span > 0.0 { (time - times[i]) / span } else { 0.0 })
+}
+
+// Value between keys i and i + 1, t along the way
+pub fn interpolate<T>(values: &[T], i: usize, t: Float, interpolation: Interpolation) -> T
+        where T: Copy + Add<T, Output = T> + Sub<T, Output = T> + Mul<Float, Output = T> {
+    let last = values.len() - 1;
+    let p1 = values[i];
+    let p2 = values[usize::min(i + 1, last)];
+
+    match interpolation {
+        Interpolation::Linear => { p1 + (p2 - p1) * t }
+        Interpolation::Spline => {
+            let p0 = values[i.saturating_sub(1)];
+            let p3 = values[usize::min(i + 2, last)];
+            let t2 = t * t;
+            let t3 = t2 * t;
+
+            // Catmull-Rom, written as offsets from p1 so it only needs add, sub and scale
+            (p2 - p0) * (0.5 * t) + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * (0.5 * t2) + (p0 * -1.0 + p1 * 3.0 - p2 * 3.0 + p3) * (0.5 * t3) + p1
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Camera path: keys with a time (seconds), camera position, point looked at, vertical field of view and focus distance
+
+#[derive(Copy, Clone)]
+pub struct CameraKey {
+    pub time: Float,
+    pub look_from: Vec3<Float>,
+    pub look_at: Vec3<Float>,
+    pub fov: Float,
+    pub focus_dist: Float
+}
+
+impl CameraKey {
+    pub fn new(time: Float, look_from: &Vec3<Float>, look_at: &Vec3<Float>, fov: Float, focus_dist: Float) -> Self {
+        Self {
+            time,
+            look_from: *look_from,
+            look_at: *look_at,
+            fov,
+            focus_dist
+        }
+    }
+}
+
+pub struct CameraPath {
+    keys: Vec<CameraKey>,
+    pub up: Vec3<Float>,
+    pub aperture: Float,
+    pub interpolation: Interpolation
+}
+
+impl CameraPath {
+    pub fn new(up: &Vec3<Float>, aperture: Float) -> Self {
+        Self {
+            keys: Vec::new(),
+            up: *up,
+            aperture,
+            interpolation: Interpolation::Linear
+        }
+    }
+
+    // Keys can be added in any order
+    pub fn add_key(&mut self, key: CameraKey) {
+        let index = self.keys.partition_point(|k| k.time <= key.time);
+        self.keys.insert(index, key);
+    }
+
+    pub fn keys(&self) -> &[CameraKey] {
+        &self.keys
+    }
+
+    // The camera settings at the given time; there has to be at least one key
+    pub fn evaluate(&self, time: Float) -> CameraKey {
+        let times: Vec<Float> = self.keys.iter().map(|k| k.time).collect();
+        let (i, t) = locate_key(&times, time);
+
+        let look_from: Vec<Vec3<Float>> = self.keys.iter().map(|k| k.look_from).collect();
+        let look_at: Vec<Vec3<Float>> = self.keys.iter().map(|k| k.look_at).collect();
+        let fov: Vec<Float> = self.keys.iter().map(|k| k.fov).collect();
+        let focus_dist: Vec<Float> = self.keys.iter().map(|k| k.focus_dist).collect();
+
+        // Splines can overshoot, keep the lens settings sane
+        CameraKey {
+            time,
+            look_from: interpolate(&look_from, i, t, self.interpolation),
+            look_at: interpolate(&look_at, i, t, self.interpolation),
+            fov: interpolate(&fov, i, t, self.interpolation).clamp(1.0, 179.0),
+            focus_dist: Float::max(interpolate(&focus_dist, i, t, self.interpolation), 0.001)
+        }
+    }
+
+    // Camera for a frame with the shutter open from time0 to time1, placed where the path is when it opens
+    pub fn camera_at(&self, time0: Float, time1: Float, aspect_ratio: Float) -> ProjectiveCamera {
+        let key = self.evaluate(time0);
+        ProjectiveCamera::new(&key.look_from, &key.look_at, &self.up, key.fov, aspect_ratio, self.aperture, key.focus_dist, time0, time1)
+    }
+}
@@ -0,0 +1,263 @@
+use crate::types::*;
+use crate::vec3::Vec3;
+use crate::color;
+use crate::utils;
+
+extern crate image;
+
+// --------------------------------------------------------------------------------------------------------------------
+// AOV types (arbitrary output variables, a.k.a. render passes)
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AovType {
+    Depth,
+    Normal,
+    Albedo,
+    Uv,
+    ObjectId,
+    MaterialId,
+    Emission,
+    Direct,
+    Indirect
+}
+
+impl AovType {
+    pub const ALL: [AovType; 9] = [
+        AovType::Depth,
+        AovType::Normal,
+        AovType::Albedo,
+        AovType::Uv,
+        AovType::ObjectId,
+        AovType::MaterialId,
+        AovType::Emission,
+        AovType::Direct,
+        AovType::Indirect
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AovType::Depth => { "depth" }
+            AovType::Normal => { "normal" }
+            AovType::Albedo => { "albedo" }
+            AovType::Uv => { "uv" }
+            AovType::ObjectId => { "object_id" }
+            AovType::MaterialId => { "material_id" }
+            AovType::Emission => { "emission" }
+            AovType::Direct => { "direct" }
+            AovType::Indirect => { "indirect" }
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<AovType> {
+        AovType::ALL.iter().find(|aov_type| aov_type.name() == name).copied()
+    }
+
+    // Channel names used when writing exr layers
+    pub fn channel_names(&self) -> &'static [&'static str] {
+        match self {
+            AovType::Depth => { &["Z"] }
+            AovType::Uv => { &["U", "V"] }
+            AovType::ObjectId | AovType::MaterialId => { &["id"] }
+            _ => { &["R", "G", "B"] }
+        }
+    }
+
+    // Depth and ids keep the first sample; averaging them across an edge gives values that belong to nothing
+    pub fn is_filterable(&self) -> bool {
+        !matches!(self, AovType::Depth | AovType::ObjectId | AovType::MaterialId)
+    }
+
+    pub fn value(&self, sample: &AovSample) -> Vec3<Float> {
+        match self {
+            AovType::Depth => { Vec3::new(sample.depth, sample.depth, sample.depth) }
+            AovType::Normal => { sample.normal }
+            AovType::Albedo => { sample.albedo }
+            AovType::Uv => { Vec3::new(sample.uv.0, sample.uv.1, 0.0) }
+            AovType::ObjectId => { Vec3::new(sample.object_id as Float, 0.0, 0.0) }
+            AovType::MaterialId => { Vec3::new(sample.material_id as Float, 0.0, 0.0) }
+            AovType::Emission => { sample.emission }
+            AovType::Direct => { sample.direct }
+            AovType::Indirect => { sample.indirect }
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// AOV sample, gathered from the first hit of a camera ray
+
+#[derive(Copy, Clone, Default)]
+pub struct AovSample {
+    pub depth: Float,
+    pub normal: Vec3<Float>,
+    pub albedo: Vec3<Float>,
+    pub uv: (Float, Float),
+    pub object_id: u32,
+    pub material_id: u32,
+    pub emission: Vec3<Float>,
+    pub direct: Vec3<Float>,
+    pub indirect: Vec3<Float>
+}
+
+impl AovSample {
+    // A camera ray that hit nothing; the background counts as emission so that
+    // emission + direct + indirect always adds up to the beauty pass
+    pub fn miss(background: &Vec3<Float>) -> Self {
+        Self {
+            depth: Float::INFINITY,
+            emission: *background,
+            ..AovSample::default()
+        }
+    }
+
+    // Sums the filterable passes, the others keep the value of the first sample
+    pub fn accumulate(&mut self, other: &AovSample) {
+        self.normal = self.normal + other.normal;
+        self.albedo = self.albedo + other.albedo;
+        self.uv = (self.uv.0 + other.uv.0, self.uv.1 + other.uv.1);
+        self.emission = self.emission + other.emission;
+        self.direct = self.direct + other.direct;
+        self.indirect = self.indirect + other.indirect;
+    }
+
+    pub fn scale(&mut self, scale: Float) {
+        self.normal = self.normal * scale;
+        self.albedo = self.albedo * scale;
+        self.uv = (self.uv.0 * scale, self.uv.1 * scale);
+        self.emission = self.emission * scale;
+        self.direct = self.direct * scale;
+        self.indirect = self.indirect * scale;
+    }
+
+    // Material ids are derived from the material's address, so they are unique (and stable) within one render.
+    // Only 24 bits are kept so the id survives being stored as a float.
+    pub fn material_id_from_ptr<T: ?Sized>(material: *const T) -> u32 {
+        let address = material as *const () as usize as u64;
+        utils::hash_u32((address as u32) ^ utils::hash_u32((address >> 32) as u32)) & 0x00ff_ffff
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// AOV buffers
+
+pub struct AovBuffers {
+    pub width: u32,
+    pub height: u32,
+    pub beauty: Vec<Vec3<Float>>,
+    pub layers: Vec<(AovType, Vec<Vec3<Float>>)>
+}
+
+impl AovBuffers {
+    pub fn new(width: u32, height: u32, aov_types: &[AovType]) -> Self {
+        let num_pixels = (width * height) as usize;
+
+        Self {
+            width,
+            height,
+            beauty: vec![Vec3::default(); num_pixels],
+            layers: aov_types.iter().map(|&aov_type| (aov_type, vec![Vec3::default(); num_pixels])).collect()
+        }
+    }
+
+    pub fn set_pixel(&mut self, index: usize, beauty: &Vec3<Float>, sample: &AovSample) {
+        self.beauty[index] = *beauty;
+        for (aov_type, pixels) in self.layers.iter_mut() {
+            pixels[index] = aov_type.value(sample);
+        }
+    }
+
+    pub fn layer(&self, aov_type: AovType) -> Option<&Vec<Vec3<Float>>> {
+        self.layers.iter().find(|layer| layer.0 == aov_type).map(|layer| &layer.1)
+    }
+
+    pub fn beauty_image(&self) -> Option<image::RgbaImage> {
+        let raw = self.beauty.iter().flat_map(|pixel| color::vec3_to_color(pixel, 1.0)).collect();
+        image::RgbaImage::from_raw(self.width, self.height, raw)
+    }
+
+    // Maps a pass into something viewable; exr output keeps the raw values instead
+    pub fn aov_image(&self, aov_type: AovType) -> Option<image::RgbaImage> {
+        let pixels = self.layer(aov_type)?;
+        let raw = match aov_type {
+            AovType::Depth => {
+                // Near is bright, far and misses fade to black
+                let max_depth = pixels.iter().map(|p| p[0]).filter(|d| d.is_finite()).fold(0.0, Float::max);
+                let scale = if max_depth > 0.0 { 1.0 / max_depth } else { 0.0 };
+                pixels.iter().flat_map(|p| {
+                    let depth = if p[0].is_finite() { 1.0 - p[0] * scale } else { 0.0 };
+                    AovBuffers::linear_color(&Vec3::new(depth, depth, depth))
+                }).collect()
+            }
+            AovType::Normal => {
+                pixels.iter().flat_map(|p| AovBuffers::linear_color(&(*p * 0.5 + Vec3::new(0.5, 0.5, 0.5)))).collect()
+            }
+            AovType::Uv => {
+                pixels.iter().flat_map(AovBuffers::linear_color).collect()
+            }
+            AovType::ObjectId | AovType::MaterialId => {
+                pixels.iter().flat_map(|p| AovBuffers::id_color(p[0] as u32)).collect()
+            }
+            _ => {
+                pixels.iter().flat_map(|p| color::vec3_to_color(p, 1.0)).collect()
+            }
+        };
+
+        image::RgbaImage::from_raw(self.width, self.height, raw)
+    }
+
+    // Writes each pass next to the main image, e.g. output.png -> output_depth.png
+    pub fn write_images(&self, filename: &str) -> image::ImageResult<Vec<String>> {
+        let path = std::path::Path::new(filename);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+        let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("png");
+
+        let mut written = Vec::new();
+        for (aov_type, _) in &self.layers {
+            let aov_filename = path.with_file_name(format!("{}_{}.{}", stem, aov_type.name(), extension));
+            self.aov_image(*aov_type).unwrap().save(&aov_filename)?;
+            written.push(aov_filename.to_string_lossy().into_owned());
+        }
+
+        Ok(written)
+    }
+
+    // Writes the beauty pass and every aov as layers of a single multi-layer exr, with unclamped linear values
+    #[cfg(feature = "openexr")]
+    pub fn write_exr(&self, filename: &str) -> Result<(), String> {
+        use exr::prelude::*;
+
+        let size = (self.width as usize, self.height as usize);
+        let make_layer = |name: &'static str, channel_names: &[&'static str], pixels: &Vec<Vec3<Float>>| {
+            let channels = channel_names.iter().enumerate().map(|(c, &channel_name)| {
+                AnyChannel::new(channel_name, FlatSamples::F32(pixels.iter().map(|p| p[c] as f32).collect()))
+            }).collect::<Vec<_>>();
+
+            Layer::new(size, LayerAttributes::named(name), Encoding::FAST_LOSSLESS, AnyChannels::sort(SmallVec::from_vec(channels)))
+        };
+
+        let mut layers = vec![make_layer("beauty", &["R", "G", "B"], &self.beauty)];
+        for (aov_type, pixels) in &self.layers {
+            layers.push(make_layer(aov_type.name(), aov_type.channel_names(), pixels));
+        }
+
+        let image = Image::from_layers(ImageAttributes::new(IntegerBounds::from_dimensions(size)), layers);
+        image.write().to_file(filename).map_err(|e| e.to_string())
+    }
+
+    fn linear_color(color: &Vec3<Float>) -> Color {
+        [
+            color::convert_to_u8_range(color[0].clamp(0.0, 0.999)),
+            color::convert_to_u8_range(color[1].clamp(0.0, 0.999)),
+            color::convert_to_u8_range(color[2].clamp(0.0, 0.999)),
+            255
+        ]
+    }
+
+    fn id_color(id: u32) -> Color {
+        if id == 0 {
+            return [0, 0, 0, 255];
+        }
+
+        let hash = utils::hash_u32(id);
+        [(hash >> 16) as u8, (hash >> 8) as u8, hash as u8, 255]
+    }
+}
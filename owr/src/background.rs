@@ -0,0 +1,126 @@
+use crate::types::*;
+use crate::vec3::Vec3;
+use crate::light::Light;
+use crate::environment::EnvironmentLight;
+
+// --------------------------------------------------------------------------------------------------------------------
+// Background trait: what a ray that leaves the scene sees, by direction
+
+pub trait Background: Sync + Send {
+    fn value(&self, direction: &Vec3<Float>) -> Vec3<Float>;
+
+    // Backgrounds that are worth sampling directly (environment maps, skies with a sun) return themselves here
+    fn as_light(&self) -> Option<&dyn Light> { Option::None }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Constant color
+
+pub struct ConstantBackground {
+    pub color: Vec3<Float>
+}
+
+impl ConstantBackground {
+    pub fn new(color: &Vec3<Float>) -> Self {
+        Self {
+            color: *color
+        }
+    }
+}
+
+impl Background for ConstantBackground {
+    fn value(&self, _direction: &Vec3<Float>) -> Vec3<Float> {
+        self.color
+    }
+}
+
+unsafe impl Sync for ConstantBackground {}
+unsafe impl Send for ConstantBackground {}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Vertical gradient, the "Ray Tracing in One Weekend" sky
+
+pub struct GradientBackground {
+    pub bottom: Vec3<Float>,
+    pub top: Vec3<Float>
+}
+
+impl GradientBackground {
+    pub fn new(bottom: &Vec3<Float>, top: &Vec3<Float>) -> Self {
+        Self {
+            bottom: *bottom,
+            top: *top
+        }
+    }
+}
+
+impl Default for GradientBackground {
+    fn default() -> Self {
+        GradientBackground::new(&Vec3::new(1.0, 1.0, 1.0), &Vec3::new(0.5, 0.7, 1.0))
+    }
+}
+
+impl Background for GradientBackground {
+    fn value(&self, direction: &Vec3<Float>) -> Vec3<Float> {
+        let t = 0.5 * (direction.unit_vector().y() + 1.0);
+        self.bottom * (1.0 - t) + self.top * t
+    }
+}
+
+unsafe impl Sync for GradientBackground {}
+unsafe impl Send for GradientBackground {}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Procedural sky: zenith color fading into a bright horizon, and a ground color below it
+
+pub struct ProceduralSky {
+    pub zenith: Vec3<Float>,
+    pub horizon: Vec3<Float>,
+    pub ground: Vec3<Float>
+}
+
+impl ProceduralSky {
+    pub fn new(zenith: &Vec3<Float>, horizon: &Vec3<Float>, ground: &Vec3<Float>) -> Self {
+        Self {
+            zenith: *zenith,
+            horizon: *horizon,
+            ground: *ground
+        }
+    }
+}
+
+impl Default for ProceduralSky {
+    fn default() -> Self {
+        ProceduralSky::new(&Vec3::new(0.25, 0.45, 0.85), &Vec3::new(0.85, 0.90, 1.00), &Vec3::new(0.30, 0.27, 0.24))
+    }
+}
+
+impl Background for ProceduralSky {
+    fn value(&self, direction: &Vec3<Float>) -> Vec3<Float> {
+        let y = direction.unit_vector().y();
+        if y >= 0.0 {
+            let t = 1.0 - (1.0 - y).powi(3);
+            self.horizon * (1.0 - t) + self.zenith * t
+        } else {
+            // Short blend so the horizon line isn't a hard edge
+            let t = 1.0 - (1.0 + y).powi(16);
+            self.horizon * (1.0 - t) + self.ground * t
+        }
+    }
+}
+
+unsafe impl Sync for ProceduralSky {}
+unsafe impl Send for ProceduralSky {}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Image map
+
+impl Background for EnvironmentLight {
+    fn value(&self, direction: &Vec3<Float>) -> Vec3<Float> {
+        self.radiance(direction)
+    }
+
+    fn as_light(&self) -> Option<&dyn Light> {
+        Some(self)
+    }
+}
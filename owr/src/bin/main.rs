@@ -1,7 +1,20 @@
 use owr::examples::*;
 use owr::log_print;
 use owr::sampling::*;
-use owr::bvh;
+use owr::aov::AovType;
+use owr::filter::{FilterType, PixelFilter};
+use owr::sampler::SamplerType;
+use owr::environment::EnvironmentLight;
+use owr::background::{Background, ConstantBackground, GradientBackground, ProceduralSky};
+use owr::sky::{self, PhysicalSky};
+use owr::vec3::Vec3;
+use owr::light::{Light, Falloff, PointLight, SpotLight, DirectionalLight, GoniometricLight};
+use owr::ies::IesProfile;
+use owr::camera::{Camera, ProjectiveCamera, FisheyeMapping, PhysicalLens, Aperture, BokehImage, ShutterCurve};
+use owr::animation::{CameraPath, CameraKey, Interpolation};
+use owr::scene::World;
+use owr::image_sequence;
+use owr::types::RaytracerParams;
 use std::env;
 use std::sync::Arc;
 
@@ -10,8 +23,191 @@ use std::time::{Instant};
 
 // --------------------------------------------------------------------------------------------------------------------
 
+// Positional arguments (scene number, build bvh) plus "--name value" options
+struct CommandLine {
+    positional: Vec<String>,
+    options: Vec<(String, String)>
+}
+
+impl CommandLine {
+    fn parse(args: &[String]) -> Self {
+        let mut positional = Vec::new();
+        let mut options = Vec::new();
+
+        let mut i = 1;
+        while i < args.len() {
+            if let Some(name) = args[i].strip_prefix("--") {
+                let has_value = i + 1 < args.len() && !args[i + 1].starts_with("--");
+                let value = if has_value { args[i + 1].clone() } else { String::new() };
+                options.push((name.to_string(), value));
+                i += if has_value { 2 } else { 1 };
+            } else {
+                positional.push(args[i].clone());
+                i += 1;
+            }
+        }
+
+        Self {
+            positional,
+            options
+        }
+    }
+
+    fn positional(&self, index: usize) -> Option<&str> {
+        self.positional.get(index).map(|s| s.as_str())
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.iter().find(|option| option.0 == name).map(|option| option.1.as_str())
+    }
+
+    // Every value of an option that can be given more than once
+    fn option_values(&self, name: &str) -> Vec<&str> {
+        self.options.iter().filter(|option| option.0 == name).map(|option| option.1.as_str()).collect()
+    }
+}
+
+fn parse_aov_list(list: &str) -> Vec<AovType> {
+    if list == "all" {
+        return AovType::ALL.to_vec();
+    }
+
+    list.split(',').map(|name| {
+        AovType::from_name(name.trim()).unwrap_or_else(|| panic!("Unknown aov '{}'", name))
+    }).collect()
+}
+
+fn parse_background(value: &str) -> Arc<dyn Background> {
+    match value {
+        "gradient" => { Arc::new(GradientBackground::default()) }
+        "sky" => { Arc::new(ProceduralSky::default()) }
+        _ => {
+            let rgb: Vec<f32> = value.split(',').map(|c| c.trim().parse().unwrap_or_else(|_| panic!("Unknown background '{}'", value))).collect();
+            if rgb.len() != 3 {
+                panic!("Unknown background '{}'", value);
+            }
+            Arc::new(ConstantBackground::new(&Vec3::new(rgb[0], rgb[1], rgb[2])))
+        }
+    }
+}
+
+fn parse_vec3(value: &str, spec: &str) -> Vec3<f32> {
+    let xyz: Vec<f32> = value.split(',').map(|c| c.trim().parse().unwrap_or_else(|_| panic!("Invalid '{}'", spec))).collect();
+    if xyz.len() != 3 {
+        panic!("Invalid '{}', expected x,y,z in '{}'", spec, value);
+    }
+    Vec3::new(xyz[0], xyz[1], xyz[2])
+}
+
+// Lights that aren't objects, separated by ':' (colors are intensity for point and spot lights, irradiance for directional):
+//   point:x,y,z:r,g,b[:falloff]                    falloff is quadratic (default), linear or constant
+//   spot:x,y,z:tx,ty,tz:r,g,b:outer[,inner][:falloff]  cone half angles in degrees, soft between inner and outer
+//   directional:dx,dy,dz:r,g,b[:diameter]          light travelling along d, the angular diameter (degrees) softens shadows
+//   ies:file.ies:x,y,z:r,g,b[:tx,ty,tz[:outer[,inner]]]  measured profile (candela times r,g,b), hanging down or aimed at t
+fn parse_light(spec: &str) -> Arc<dyn Light> {
+    let parts: Vec<&str> = spec.split(':').collect();
+    let falloff = |index: usize| {
+        parts.get(index).map(|name| Falloff::from_name(name).unwrap_or_else(|| panic!("Unknown falloff '{}'", name))).unwrap_or_default()
+    };
+
+    match (parts[0], parts.len()) {
+        ("point", 3..=4) => {
+            let mut light = PointLight::new(&parse_vec3(parts[1], spec), &parse_vec3(parts[2], spec));
+            light.falloff = falloff(3);
+            Arc::new(light)
+        }
+        ("spot", 5..=6) => {
+            let angles: Vec<f32> = parts[4].split(',').map(|c| c.trim().parse().unwrap_or_else(|_| panic!("Invalid light '{}'", spec))).collect();
+            let outer = angles[0];
+            let inner = angles.get(1).copied().unwrap_or(outer);
+            let mut light = SpotLight::new(&parse_vec3(parts[1], spec), &parse_vec3(parts[2], spec), &parse_vec3(parts[3], spec), outer, inner);
+            light.falloff = falloff(5);
+            Arc::new(light)
+        }
+        ("directional", 3..=4) => {
+            let diameter = parts.get(3).map(|d| d.parse().unwrap_or_else(|_| panic!("Invalid light '{}'", spec))).unwrap_or(0.0);
+            Arc::new(DirectionalLight::new(&parse_vec3(parts[1], spec), &parse_vec3(parts[2], spec), diameter))
+        }
+        ("ies", 4..=6) => {
+            let profile = Arc::new(IesProfile::load(parts[1]).unwrap_or_else(|e| panic!("Failed to load '{}': {}", parts[1], e)));
+            let position = parse_vec3(parts[2], spec);
+            let intensity = parse_vec3(parts[3], spec);
+            match parts.get(4) {
+                Some(target) => {
+                    let cone = parts.get(5).map(|angles| {
+                        let angles: Vec<f32> = angles.split(',').map(|c| c.trim().parse().unwrap_or_else(|_| panic!("Invalid light '{}'", spec))).collect();
+                        (angles[0], angles.get(1).copied().unwrap_or(angles[0]))
+                    });
+                    Arc::new(GoniometricLight::new_spot(&position, &parse_vec3(target, spec), profile, &intensity, cone))
+                }
+                _ => {
+                    Arc::new(GoniometricLight::new_point(&position, profile, &intensity))
+                }
+            }
+        }
+        _ => {
+            panic!("Invalid light '{}'", spec);
+        }
+    }
+}
+
+// Preetham sky with the sun placed by "--sun-elevation 30 --sun-azimuth 135" (degrees, azimuth from north
+// towards east), or by place and time: "--sun-location 52.37,4.89 --sun-date 2022-06-21 --sun-time 14:30 --sun-timezone 2"
+fn parse_physical_sky(command_line: &CommandLine) -> PhysicalSky {
+    let number = |name: &str, default: f32| {
+        command_line.option(name).map(|arg| arg.parse().unwrap_or_else(|_| panic!("Invalid --{} '{}'", name, arg))).unwrap_or(default)
+    };
+
+    let (elevation, azimuth) = match command_line.option("sun-location") {
+        Some(location) => {
+            let lat_long: Vec<f32> = location.split(',').map(|c| c.trim().parse().unwrap_or_else(|_| panic!("Invalid --sun-location '{}'", location))).collect();
+            if lat_long.len() != 2 {
+                panic!("Invalid --sun-location '{}', expected latitude,longitude", location);
+            }
+
+            let date = command_line.option("sun-date").unwrap_or("2022-06-21");
+            let ymd: Vec<i32> = date.split('-').map(|c| c.parse().unwrap_or_else(|_| panic!("Invalid --sun-date '{}'", date))).collect();
+            if ymd.len() != 3 {
+                panic!("Invalid --sun-date '{}', expected year-month-day", date);
+            }
+
+            let time = command_line.option("sun-time").unwrap_or("12:00");
+            let hm: Vec<f32> = time.split(':').map(|c| c.parse().unwrap_or_else(|_| panic!("Invalid --sun-time '{}'", time))).collect();
+            let hour = hm[0] + hm.get(1).unwrap_or(&0.0) / 60.0;
+
+            let (elevation, azimuth) = sky::sun_position(lat_long[0], lat_long[1], (ymd[0], ymd[1] as u32, ymd[2] as u32), hour, number("sun-timezone", 0.0));
+            log_print!("Sun elevation {:.1}, azimuth {:.1}\n", elevation, azimuth);
+            (elevation, azimuth)
+        }
+        None => {
+            (number("sun-elevation", 45.0), number("sun-azimuth", 135.0))
+        }
+    };
+
+    PhysicalSky::new(elevation, azimuth, number("turbidity", 3.0), number("sky-intensity", 1.0), &Vec3::new(0.2, 0.2, 0.2))
+}
+
+// Camera key for an animation, "time:x,y,z:tx,ty,tz:fov[:focus]": time in seconds, camera position, point looked at,
+// vertical field of view in degrees, and focus distance (the distance to the point looked at by default)
+fn parse_camera_key(spec: &str) -> CameraKey {
+    let parts: Vec<&str> = spec.split(':').collect();
+    if parts.len() < 4 {
+        panic!("Invalid --camera-key '{}', expected time:x,y,z:tx,ty,tz:fov[:focus]", spec);
+    }
+
+    let number = |part: &str| -> f32 { part.trim().parse().unwrap_or_else(|_| panic!("Invalid --camera-key '{}'", spec)) };
+    let look_from = parse_vec3(parts[1], spec);
+    let look_at = parse_vec3(parts[2], spec);
+    let focus_dist = parts.get(4).map(|part| number(part)).unwrap_or_else(|| (look_at - look_from).length());
+
+    CameraKey::new(number(parts[0]), &look_from, &look_at, number(parts[3]), focus_dist)
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+
 pub fn main() {
     let args: Vec<String> = env::args().collect();
+    let command_line = CommandLine::parse(&args);
 
     // Some default parameters. TODO: make them configurable from the command-line
     let image_width = 400;
@@ -22,24 +218,304 @@ pub fn main() {
     let enable_parallel = true;
     let enable_progress_bar = true;
 
+    // Render passes, e.g. "--aov depth,normal,albedo" (or "--aov all"), written as
+    // separate images next to the output, or as layers of one exr with "--aov-output exr"
+    let aov_types = command_line.option("aov").map(parse_aov_list).unwrap_or_default();
+    let aov_output = command_line.option("aov-output").unwrap_or("separate");
+    if cfg!(not(feature = "openexr")) && aov_output == "exr" {
+        panic!("Can't write '--aov-output exr', built without the openexr feature");
+    }
+
     // Select scene from commandline args
-    let scene_num = if args.len() > 1 { args[1].parse().unwrap() } else { 0 };
-    let example_scene = scene_select(scene_num, image_width, image_height, samples_per_pixel, max_depth, image::open("./images/earthmap.jpeg").unwrap().to_rgba8());
+    let scene_num = command_line.positional(0).map(|arg| arg.parse().unwrap()).unwrap_or(0);
+    let mut example_scene = scene_select(scene_num, image_width, image_height, samples_per_pixel, max_depth, image::open("./images/earthmap.jpeg").unwrap().to_rgba8());
+
+    // Photographic lens on the example camera, e.g. "--focal-length 50 --f-number 1.8 --focus-distance 10", optionally
+    // with "--sensor-width 23.6" (millimeters, full frame by default). Without --focus-distance, the camera keeps its own.
+    if let Some(focal_length) = command_line.option("focal-length") {
+        let number = |name: &str, default: f32| {
+            command_line.option(name).map(|arg| arg.parse().unwrap_or_else(|_| panic!("Invalid --{} '{}'", name, arg))).unwrap_or(default)
+        };
+        let focal_length = focal_length.parse().unwrap_or_else(|_| panic!("Invalid --focal-length '{}'", focal_length));
+
+        let mut lens = PhysicalLens::new(focal_length, number("f-number", 2.8), number("focus-distance", example_scene.1.focus_distance()));
+        lens.sensor_width = number("sensor-width", lens.sensor_width);
+        lens.aperture = example_scene.1.aperture.clone();
+        example_scene.1 = example_scene.1.to_physical(&lens, example_scene.0.aspect_ratio);
+    }
+
+    // Aperture shape, e.g. "--aperture-blades 6 --aperture-rotation 15", or a grayscale mask "--bokeh heart.png"
+    if let Some(blades) = command_line.option("aperture-blades") {
+        let blades = blades.parse().unwrap_or_else(|_| panic!("Invalid --aperture-blades '{}'", blades));
+        let rotation = command_line.option("aperture-rotation").map(|arg| arg.parse().unwrap_or_else(|_| panic!("Invalid --aperture-rotation '{}'", arg))).unwrap_or(0.0);
+        example_scene.1.aperture = Aperture::Polygon { blades, rotation };
+    }
+    if let Some(bokeh_filename) = command_line.option("bokeh") {
+        let bokeh = BokehImage::load(bokeh_filename).unwrap_or_else(|e| panic!("Failed to load '{}': {}", bokeh_filename, e));
+        example_scene.1.aperture = Aperture::Image(Arc::new(bokeh));
+    }
+
+    // Shutter curve, "--shutter-curve triangle" or openness over the open time "--shutter-curve 0.2,1,1,0.5" (box by default),
+    // and rolling shutter readout time "--rolling-shutter 0.5", in scene time (frames when rendering an animation)
+    if let Some(curve) = command_line.option("shutter-curve") {
+        example_scene.1.shutter.curve = ShutterCurve::from_name(curve).unwrap_or_else(|| {
+            let values: Vec<f32> = curve.split(',').map(|c| c.trim().parse().unwrap_or_else(|_| panic!("Invalid --shutter-curve '{}'", curve))).collect();
+            ShutterCurve::custom(&values)
+        });
+    }
+    if let Some(rolling) = command_line.option("rolling-shutter") {
+        example_scene.1.shutter.rolling = rolling.parse().unwrap_or_else(|_| panic!("Invalid --rolling-shutter '{}'", rolling));
+    }
+
+    // Pixel reconstruction filter, e.g. "--filter gaussian --filter-radius 1.5"
+    if let Some(filter_name) = command_line.option("filter") {
+        let filter_type = FilterType::from_name(filter_name).unwrap_or_else(|| panic!("Unknown filter '{}'", filter_name));
+        let radius: f32 = command_line.option("filter-radius").map(|arg| arg.parse().unwrap_or_else(|_| panic!("Invalid --filter-radius '{}'", arg))).unwrap_or_else(|| filter_type.default_radius());
+        if radius <= 0.0 {
+            panic!("Invalid --filter-radius '{}', it has to be more than 0", radius);
+        }
+        example_scene.0.filter = PixelFilter::new(filter_type, radius);
+    }
+
+    // Sample generator, e.g. "--sampler sobol"
+    if let Some(sampler_name) = command_line.option("sampler") {
+        example_scene.0.sampler = SamplerType::from_name(sampler_name).unwrap_or_else(|| panic!("Unknown sampler '{}'", sampler_name));
+    }
+
+    // Global seed, renders with the same seed are identical, e.g. "--seed 42"
+    if let Some(seed) = command_line.option("seed") {
+        example_scene.0.seed = seed.parse().unwrap_or_else(|_| panic!("Invalid --seed '{}'", seed));
+    }
+
+    // Background, e.g. "--background sky", "--background gradient" or a constant color "--background 0.1,0.1,0.1",
+    // "--background physical" for the physical sky and sun (see parse_physical_sky)
+    if let Some(background) = command_line.option("background") {
+        example_scene.2.background = if background == "physical" { Arc::new(parse_physical_sky(&command_line)) } else { parse_background(background) };
+    }
+
+    // Environment map lighting, e.g. "--environment sky.hdr --environment-rotation 90 --environment-intensity 2"
+    if let Some(environment_filename) = command_line.option("environment") {
+        let rotation = command_line.option("environment-rotation").map(|arg| arg.parse().unwrap_or_else(|_| panic!("Invalid --environment-rotation '{}'", arg))).unwrap_or(0.0);
+        let intensity = command_line.option("environment-intensity").map(|arg| arg.parse().unwrap_or_else(|_| panic!("Invalid --environment-intensity '{}'", arg))).unwrap_or(1.0);
+        let environment = EnvironmentLight::load(environment_filename, rotation, intensity).unwrap_or_else(|e| panic!("Failed to load '{}': {}", environment_filename, e));
+        example_scene.2.background = Arc::new(environment);
+    }
+
+    // Extra lights, e.g. "--light point:0,5,0:20,20,20 --light directional:-1,-1,0:1,1,1:0.53" (see parse_light)
+    for light in command_line.option_values("light") {
+        example_scene.2.lights.push(parse_light(light));
+    }
+
+    // Next event estimation (direct light sampling), on by default: "--nee off" to disable
+    if let Some(nee) = command_line.option("nee") {
+        example_scene.0.next_event_estimation = nee != "off";
+    }
+
+    // Tag top-level objects so the object id pass can tell them apart
+    if aov_types.contains(&AovType::ObjectId) {
+        example_scene.2.objects = example_scene.2.objects.with_object_ids();
+    }
 
     // Build bvh?
-    let build_bvh = if args.len() > 2 { args[2].parse().unwrap() } else { true };
+    let build_bvh = command_line.positional(1).map(|arg| arg.parse().unwrap()).unwrap_or(true);
     log_print!("Build bvh: {}\n", build_bvh);
 
-    // Build bvh if set
-    let hittables = match build_bvh {
-        true => { bvh::BvhNode::build_bvh(&example_scene.2, 0.0, 0.0) },
-        _ => { Arc::new(example_scene.2) }
+    // Animation, e.g. "--frames 0-47 --fps 24" renders output_0000.png to output_0047.png, with the shutter open for
+    // "--shutter 0.5" of each frame. The camera follows "--camera-key" keys (see parse_camera_key) if there are any,
+    // "--camera-interpolation spline" for a smooth path. The scene and bvh are built once for all frames.
+    let frames = command_line.option("frames").map(|arg| {
+        let range: Vec<u32> = arg.split('-').map(|c| c.parse().unwrap_or_else(|_| panic!("Invalid --frames '{}'", arg))).collect();
+        (range[0], *range.last().unwrap())
+    });
+    let fps: f32 = command_line.option("fps").map(|arg| arg.parse().unwrap_or_else(|_| panic!("Invalid --fps '{}'", arg))).unwrap_or(24.0);
+    let shutter: f32 = command_line.option("shutter").map(|arg| arg.parse().unwrap_or_else(|_| panic!("Invalid --shutter '{}'", arg))).unwrap_or(0.5);
+
+    let camera_keys = command_line.option_values("camera-key");
+    let camera_path = if camera_keys.is_empty() { Option::None } else {
+        let mut camera_path = CameraPath::new(&Vec3::new(0.0, 1.0, 0.0), example_scene.1.aperture_diameter());
+        for key in camera_keys {
+            camera_path.add_key(parse_camera_key(key));
+        }
+        if let Some(interpolation) = command_line.option("camera-interpolation") {
+            camera_path.interpolation = Interpolation::from_name(interpolation).unwrap_or_else(|| panic!("Unknown interpolation '{}'", interpolation));
+        }
+        Some(camera_path)
+    };
+
+    // Shutter of a frame, times are in seconds
+    let frame_shutter = |frame: u32| {
+        let mut frame_shutter = example_scene.1.shutter.clone();
+        frame_shutter.open = (frame as f32) / fps;
+        frame_shutter.close = ((frame as f32) + shutter) / fps;
+        frame_shutter.rolling = example_scene.1.shutter.rolling / fps;
+        frame_shutter
+    };
+
+    // Build bvh if set, for the time the shutter is open over all frames
+    let (time0, time1) = match frames {
+        Some((first, last)) => { (frame_shutter(first).interval().0, frame_shutter(last).interval().1) }
+        _ => { example_scene.1.shutter.interval() }
+    };
+    let world = example_scene.2.build_world(build_bvh, time0, time1);
+
+    // Turntable, e.g. "--turntable 36": the camera circles the point it looks at in that many frames, written as an
+    // animated image (output.gif, or output.apng with "--turntable-output apng") playing at --fps, plus numbered pngs
+    // with "--png-sequence". The scene stands still.
+    let turntable_frames: Option<u32> = command_line.option("turntable").map(|arg| arg.parse().unwrap_or_else(|_| panic!("Invalid --turntable '{}'", arg)));
+    let turntable_output = command_line.option("turntable-output").unwrap_or("gif");
+    let write_frames = turntable_frames.is_none() || command_line.option("png-sequence").is_some();
+
+    let frame_list: Vec<Option<u32>> = match (turntable_frames, frames) {
+        (Some(count), _) => { (0..count).map(Some).collect() }
+        (_, Some((first, last))) => { (first..=last).map(Some).collect() }
+        _ => { vec![Option::None] }
     };
 
-    // Render and write out image
     log_print!("Rendering scene {}...\n", scene_num);
+    let mut turntable_images = Vec::new();
+    for frame in frame_list {
+        let mut camera = match frame {
+            Some(frame) if turntable_frames.is_some() => {
+                example_scene.1.orbit(360.0 * (frame as f32) / (turntable_frames.unwrap() as f32))
+            }
+            Some(frame) => {
+                let shutter = frame_shutter(frame);
+                let mut camera = match &camera_path {
+                    Some(camera_path) => {
+                        let mut camera = camera_path.camera_at(shutter.open, shutter.close, example_scene.0.aspect_ratio);
+                        camera.aperture = example_scene.1.aperture.clone();
+                        camera
+                    }
+                    _ => { example_scene.1.clone() }
+                };
+                camera.shutter = shutter;
+                camera
+            }
+            _ => { example_scene.1.clone() }
+        };
+
+        // Focus on what's seen through a pixel, e.g. "--autofocus 200,150"
+        if let Some(pixel) = command_line.option("autofocus") {
+            let xy: Vec<f32> = pixel.split(',').map(|c| c.trim().parse().unwrap_or_else(|_| panic!("Invalid --autofocus '{}'", pixel))).collect();
+            if xy.len() != 2 {
+                panic!("Invalid --autofocus '{}', expected x,y", pixel);
+            }
+
+            let s = (xy[0] + 0.5) / ((example_scene.0.image_width - 1) as f32);
+            let t = (xy[1] + 0.5) / ((example_scene.0.image_height - 1) as f32);
+            match camera.autofocus(s, t, &world) {
+                Some(focus_distance) => { log_print!("Focus distance {:.3}\n", focus_distance); }
+                _ => { log_print!("Nothing to focus on at {}\n", pixel); }
+            }
+        }
+
+        let filename = match frame {
+            Some(frame) => { frame_filename(output_filename, frame) }
+            _ => { output_filename.to_string() }
+        };
+        let camera = camera_model(&command_line, &camera, example_scene.0.aspect_ratio);
+        let output = FrameOutput {
+            filename: if write_frames { Some(&filename) } else { Option::None },
+            aov_types: &aov_types,
+            aov_output
+        };
+        let image = render_frame(enable_parallel, enable_progress_bar, &example_scene.0, camera.as_ref(), &world, &output);
+
+        if turntable_frames.is_some() {
+            turntable_images.push(image);
+        }
+    }
+
+    if turntable_frames.is_some() {
+        let path = std::path::Path::new(output_filename);
+        let (animation_filename, result) = match turntable_output {
+            "apng" => {
+                let filename = path.with_extension("apng").to_str().unwrap().to_string();
+                let result = image_sequence::write_apng(&filename, &turntable_images, fps);
+                (filename, result)
+            }
+            _ => {
+                let filename = path.with_extension("gif").to_str().unwrap().to_string();
+                let result = image_sequence::write_gif(&filename, &turntable_images, fps);
+                (filename, result)
+            }
+        };
+        result.unwrap_or_else(|e| panic!("Failed to write '{}': {}", animation_filename, e));
+        log_print!("Turntable written to {}\n", animation_filename);
+    }
+}
+
+// Camera model to render with, from the position and direction of the given camera: "--orthographic 12" for a view
+// 12 units wide, "--panorama" for a 360 degree equirectangular image (best at a 2:1 size), or "--fisheye equidistant" /
+// "--fisheye equisolid" with "--fisheye-fov 180". Otherwise the camera itself.
+fn camera_model(command_line: &CommandLine, camera: &ProjectiveCamera, aspect_ratio: f32) -> Box<dyn Camera> {
+    if let Some(view_width) = command_line.option("orthographic") {
+        let view_width = view_width.parse().unwrap_or_else(|_| panic!("Invalid --orthographic '{}'", view_width));
+        Box::new(camera.to_orthographic(view_width, aspect_ratio))
+    } else if command_line.option("panorama").is_some() {
+        Box::new(camera.to_equirectangular())
+    } else if let Some(mapping) = command_line.option("fisheye") {
+        let mapping = FisheyeMapping::from_name(mapping).unwrap_or_else(|| panic!("Unknown fisheye mapping '{}'", mapping));
+        let fov = command_line.option("fisheye-fov").map(|arg| arg.parse().unwrap_or_else(|_| panic!("Invalid --fisheye-fov '{}'", arg))).unwrap_or(180.0);
+        Box::new(camera.to_fisheye(mapping, fov, aspect_ratio))
+    } else {
+        Box::new(camera.clone())
+    }
+}
+
+// "output.png" and frame 7 make "output_0007.png"
+fn frame_filename(output_filename: &str, frame: u32) -> String {
+    let path = std::path::Path::new(output_filename);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("png");
+
+    path.with_file_name(format!("{}_{:04}.{}", stem, frame, extension)).to_str().unwrap().to_string()
+}
+
+// Where a rendered frame goes: nowhere without a filename, and which aovs are written next to it and how
+struct FrameOutput<'a> {
+    filename: Option<&'a str>,
+    aov_types: &'a [AovType],
+    aov_output: &'a str
+}
+
+// Renders one image and writes it out when given a filename, with its aovs when there are any
+fn render_frame(enable_parallel: bool, enable_progress_bar: bool, params: &RaytracerParams, camera: &dyn Camera, world: &World,
+        output: &FrameOutput) -> image::RgbaImage {
     let now = Instant::now();
-    let final_image = render_image(enable_parallel, enable_progress_bar, &example_scene.0, &example_scene.1, &hittables);
-    final_image.unwrap().save(output_filename).unwrap();
+    let output_filename = match output.filename {
+        Some(output_filename) if !output.aov_types.is_empty() => { output_filename }
+        _ => {
+            let final_image = render_image(enable_parallel, enable_progress_bar, params, camera, world).unwrap();
+            match output.filename {
+                Some(output_filename) => {
+                    final_image.save(output_filename).unwrap();
+                    log_print!("Completed in {}s. Results written to {}\n", now.elapsed().as_secs_f32(), output_filename);
+                }
+                _ => { log_print!("Completed in {}s\n", now.elapsed().as_secs_f32()); }
+            }
+            return final_image;
+        }
+    };
+
+    let buffers = render_aovs(enable_parallel, enable_progress_bar, params, camera, world, output.aov_types);
+    let final_image = buffers.beauty_image().unwrap();
+    final_image.save(output_filename).unwrap();
     log_print!("Completed in {}s. Results written to {}\n", now.elapsed().as_secs_f32(), output_filename);
+
+    match output.aov_output {
+        #[cfg(feature = "openexr")]
+        "exr" => {
+            let exr_filename = std::path::Path::new(output_filename).with_extension("exr");
+            buffers.write_exr(exr_filename.to_str().unwrap()).unwrap();
+            log_print!("Aov layers written to {}\n", exr_filename.display());
+        }
+        _ => {
+            for aov_filename in buffers.write_images(output_filename).unwrap() {
+                log_print!("Aov written to {}\n", aov_filename);
+            }
+        }
+    }
+
+    final_image
 }
@@ -22,8 +22,8 @@ impl BvhNode {
     }
 
     fn build(src_objects: &Vec<Arc<dyn Hittable>>, start: usize, end: usize, time0: Float, time1: Float) -> Arc<dyn Hittable> {
-         // Choose random axis to split objects
-         let axis = rand_u32() % 2;
+         // Choose random axis to split objects (hashed from the range, so every build comes out the same)
+         let axis = hash_combine(&[start as u32, end as u32]) % 2;
          let compare_nodes = match axis { 
              0 => { BvhNode::box_x_compare }
              1 => { BvhNode::box_y_compare }
@@ -2,12 +2,213 @@ use crate::vec3::{Vec3};
 use crate::ray::{Ray};
 use crate::utils;
 use crate::types::*;
+use crate::sampler::Sampler;
+use crate::distribution::{Distribution1D, Distribution2D};
+use crate::scene::World;
+
+use std::sync::Arc;
+
+// --------------------------------------------------------------------------------------------------------------------
+// Camera models turn a position on the image, (0, 0) top left to (1, 1) bottom right, into a ray. Positions the model
+// doesn't cover (outside a fisheye circle) give no ray and stay black.
+//
+// Every model draws a 2D lens sample and then a 1D time sample, used or not, so the sampler dimensions line up.
+
+pub trait Camera: Sync + Send {
+    fn get_ray(&self, s: Float, t: Float, sampler: &mut dyn Sampler) -> Option<Ray<Float>>;
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Shutter: when it opens and closes, and how far open it is in between. A box curve is fully open the whole time, a
+// triangle opens and closes gradually (like a rotating disc shutter crossing the sensor), a custom curve is a list of
+// openness values spread evenly over the interval. Rays get their time in proportion to how far open the shutter is.
+//
+// With a rolling shutter the rows are exposed one after the other, top to bottom, each for close - open but starting
+// up to rolling later, the way CMOS sensors read out. Fast motion then leans and wobbles like in real footage.
+
+#[derive(Clone)]
+pub enum ShutterCurve {
+    Box,
+    Triangle,
+    Custom(Arc<Distribution1D>)
+}
+
+impl ShutterCurve {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "box" => { Some(ShutterCurve::Box) }
+            "triangle" => { Some(ShutterCurve::Triangle) }
+            _ => { Option::None }
+        }
+    }
+
+    pub fn custom(values: &[Float]) -> Self {
+        ShutterCurve::Custom(Arc::new(Distribution1D::new(values)))
+    }
+
+    // Fraction of the open time, picked in proportion to the curve
+    pub fn sample(&self, u: Float) -> Float {
+        match self {
+            ShutterCurve::Box => { u }
+            ShutterCurve::Triangle => {
+                if u < 0.5 { (u * 0.5).sqrt() } else { 1.0 - ((1.0 - u) * 0.5).sqrt() }
+            }
+            ShutterCurve::Custom(distribution) => { distribution.sample_continuous(u).0 }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Shutter {
+    pub open: Float,
+    pub close: Float,
+    pub curve: ShutterCurve,
+    pub rolling: Float
+}
+
+impl Shutter {
+    pub fn new(open: Float, close: Float) -> Self {
+        Self {
+            open,
+            close,
+            curve: ShutterCurve::Box,
+            rolling: 0.0
+        }
+    }
+
+    // Time for a ray through image row t (0 top, 1 bottom)
+    pub fn sample_time(&self, t: Float, u: Float) -> Float {
+        let row_open = self.open + self.rolling * t.clamp(0.0, 1.0);
+        row_open + self.curve.sample(u) * (self.close - self.open)
+    }
+
+    // From the first row opening to the last row closing
+    pub fn interval(&self) -> (Float, Float) {
+        (self.open, self.close + self.rolling)
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Aperture shape of a lens, which is the shape out of focus highlights (bokeh) take on. Polygons have their corners on
+// the aperture circle, like the iris of a lens with that many straight blades; rotation is in degrees.
+
+#[derive(Clone)]
+pub enum Aperture {
+    Circle,
+    Polygon { blades: u32, rotation: Float },
+    Image(Arc<BokehImage>)
+}
+
+impl Aperture {
+    // Point on the aperture, inside the unit circle
+    pub fn sample(&self, u: (Float, Float)) -> Vec3<Float> {
+        match self {
+            Aperture::Circle => { utils::unitdisk_vec3_from(u) }
+            Aperture::Polygon { blades, rotation } => {
+                // Pick one of the triangles between the center and two neighbouring corners, then a point in it
+                let blades = u32::max(*blades, 3);
+                let scaled = u.0 * (blades as Float);
+                let blade = Float::min(scaled.floor(), (blades - 1) as Float);
+                let u0 = scaled - blade;
+
+                let corner = |i: Float| {
+                    let angle = rotation.to_radians() + 2.0 * (std::f32::consts::PI as Float) * i / (blades as Float);
+                    Vec3::new(angle.cos(), angle.sin(), 0.0)
+                };
+                let su = u0.sqrt();
+
+                corner(blade) * (su * (1.0 - u.1)) + corner(blade + 1.0) * (su * u.1)
+            }
+            Aperture::Image(bokeh) => { bokeh.sample(u) }
+        }
+    }
+}
+
+// Grayscale mask over the square around the aperture circle, bright where the lens lets light through. Points are
+// picked in proportion to the brightness, so the mask changes the shape of the bokeh but not the exposure.
+pub struct BokehImage {
+    distribution: Distribution2D
+}
+
+impl BokehImage {
+    // Values are row major from the top, width x height
+    pub fn new(width: usize, height: usize, values: &[Float]) -> Self {
+        Self {
+            distribution: Distribution2D::new(values, width, height)
+        }
+    }
+
+    // Any image the image crate reads, linearized with the same gamma of 2 the output uses
+    pub fn load(filename: &str) -> Result<Self, String> {
+        let image = image::open(filename).map_err(|e| e.to_string())?.to_luma8();
+        if image.width() == 0 || image.height() == 0 {
+            return Err(format!("Bokeh image '{}' is empty", filename));
+        }
+
+        let values: Vec<Float> = image.pixels().map(|p| { let c = (p[0] as Float) / 255.0; c * c }).collect();
+        Ok(BokehImage::new(image.width() as usize, image.height() as usize, &values))
+    }
+
+    pub fn sample(&self, u: (Float, Float)) -> Vec3<Float> {
+        let ((x, y), _pdf) = self.distribution.sample_continuous(u);
+        Vec3::new(2.0 * x - 1.0, 1.0 - 2.0 * y, 0.0)
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Photographic description of a lens: focal length and sensor width in millimeters (36 is full frame), f-number, and
+// focus distance in scene units. The sensor height follows from the image aspect ratio. Scenes are taken to be in
+// meters for the size of the aperture (focal length / f-number).
+
+#[derive(Clone)]
+pub struct PhysicalLens {
+    pub focal_length: Float,
+    pub sensor_width: Float,
+    pub f_number: Float,
+    pub focus_distance: Float,
+    pub aperture: Aperture
+}
+
+impl PhysicalLens {
+    pub fn new(focal_length: Float, f_number: Float, focus_distance: Float) -> Self {
+        Self {
+            focal_length,
+            sensor_width: 36.0,
+            f_number,
+            focus_distance,
+            aperture: Aperture::Circle
+        }
+    }
+
+    pub fn vertical_fov(&self, aspect_ratio: Float) -> Float {
+        let sensor_height = self.sensor_width / aspect_ratio;
+        2.0 * (sensor_height / (2.0 * self.focal_length)).atan().to_degrees()
+    }
+
+    // Diameter of the entrance pupil, in meters
+    pub fn aperture_diameter(&self) -> Float {
+        self.focal_length / self.f_number.max(0.1) / 1000.0
+    }
+}
 
 // --------------------------------------------------------------------------------------------------------------------
+// Projective camera
+
+// Perspective cameras shoot rays out of the lens towards the focus plane, orthographic ones shoot parallel rays
+// from a flat rectangle around look_from, so distance doesn't change the size of things
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Projection {
+    Perspective,
+    Orthographic
+}
 
 #[allow(dead_code)]
-pub struct Camera {
+#[derive(Clone)]
+pub struct ProjectiveCamera {
+    projection: Projection,
     origin: Vec3<Float>,
+    look_at: Vec3<Float>,
+    up: Vec3<Float>,
     lower_left_corner: Vec3<Float>,
     horizontal: Vec3<Float>,
     vertical: Vec3<Float>,
@@ -15,14 +216,14 @@ pub struct Camera {
     v: Vec3<Float>,
     w: Vec3<Float>,
     lens_radius: Float,
-    time0: Float,
-    time1: Float,
-    background: Vec3<Float>
+    focus_dist: Float,
+    pub shutter: Shutter,
+    pub aperture: Aperture
 }
 
-impl Camera {
+impl ProjectiveCamera {
     pub fn new(look_from: &Vec3<Float>, look_at: &Vec3<Float>, up: &Vec3<Float>, fov: Float, 
-            aspect_ratio: Float, aperture: Float, focus_dist: Float, time0: Float, time1: Float, background: Vec3<Float>) -> Self {
+            aspect_ratio: Float, aperture: Float, focus_dist: Float, time0: Float, time1: Float) -> Self {
         let theta = fov.to_radians();
         let h = (theta/2.0).tan();
         let viewport_height = 2.0 * h * -1.0;
@@ -39,7 +240,10 @@ impl Camera {
         let lens_radius = aperture / 2.0;
 
         Self {
+            projection: Projection::Perspective,
             origin,
+            look_at: *look_at,
+            up: *up,
             horizontal,
             vertical,
             lower_left_corner,
@@ -47,24 +251,303 @@ impl Camera {
             v,
             w,
             lens_radius,
-            time0,
-            time1,
-            background
+            focus_dist,
+            shutter: Shutter::new(time0, time1),
+            aperture: Aperture::Circle
         }
     }
 
-    pub fn get_ray(&self, s: Float, t: Float) -> Ray<Float> {
-        let rd = utils::random_unitdisk_vec3() * self.lens_radius;
-        let offset = self.u*rd.x() + self.v*rd.y();
+    // Perspective camera with the field of view, depth of field and aperture shape of the lens
+    pub fn new_physical(look_from: &Vec3<Float>, look_at: &Vec3<Float>, up: &Vec3<Float>, lens: &PhysicalLens,
+            aspect_ratio: Float, time0: Float, time1: Float) -> Self {
+        let mut camera = ProjectiveCamera::new(look_from, look_at, up, lens.vertical_fov(aspect_ratio), aspect_ratio,
+            lens.aperture_diameter(), lens.focus_distance, time0, time1);
+        camera.aperture = lens.aperture.clone();
+
+        camera
+    }
 
-        Ray::<Float> { 
-            orig: self.origin + offset,
-            dir: self.lower_left_corner + self.horizontal*s + self.vertical*t - self.origin - offset,
-            time: utils::random_range(self.time0, self.time1)
+    // Orthographic camera looking from look_from towards look_at, view_width wide (in scene units)
+    pub fn new_orthographic(look_from: &Vec3<Float>, look_at: &Vec3<Float>, up: &Vec3<Float>, view_width: Float,
+            aspect_ratio: Float, time0: Float, time1: Float) -> Self {
+        let w = (*look_from - *look_at).unit_vector();
+        let u = up.cross(&w).unit_vector();
+        let v = w.cross(&u);
+
+        // Rows go down the image, like the perspective camera
+        let origin = *look_from;
+        let horizontal = u * view_width;
+        let vertical = v * (-view_width / aspect_ratio);
+        let lower_left_corner = origin - horizontal*0.5 - vertical*0.5;
+
+        Self {
+            projection: Projection::Orthographic,
+            origin,
+            look_at: *look_at,
+            up: *up,
+            horizontal,
+            vertical,
+            lower_left_corner,
+            u,
+            v,
+            w,
+            lens_radius: 0.0,
+            focus_dist: 1.0,
+            shutter: Shutter::new(time0, time1),
+            aperture: Aperture::Circle
         }
     }
 
-    pub fn get_background(&self) -> Vec3<Float> {
-        self.background
+    // The same camera (position, direction, shutter) with an orthographic projection view_width wide
+    pub fn to_orthographic(&self, view_width: Float, aspect_ratio: Float) -> Self {
+        let mut camera = ProjectiveCamera::new_orthographic(&self.origin, &self.look_at, &self.up, view_width, aspect_ratio, 0.0, 0.0);
+        camera.shutter = self.shutter.clone();
+
+        camera
     }
-}
\ No newline at end of file
+
+    // A 360 degree panorama taken from this camera's position, centered on its view direction
+    pub fn to_equirectangular(&self) -> EquirectangularCamera {
+        let mut camera = EquirectangularCamera::new(&self.origin, &self.look_at, &self.up, 0.0, 0.0);
+        camera.shutter = self.shutter.clone();
+
+        camera
+    }
+
+    // A fisheye view from this camera's position and direction, fov degrees across the image circle
+    pub fn to_fisheye(&self, mapping: FisheyeMapping, fov: Float, aspect_ratio: Float) -> FisheyeCamera {
+        let mut camera = FisheyeCamera::new(&self.origin, &self.look_at, &self.up, mapping, fov, aspect_ratio, 0.0, 0.0);
+        camera.shutter = self.shutter.clone();
+
+        camera
+    }
+
+    // The same camera (position, direction, shutter) looking through the given lens
+    pub fn to_physical(&self, lens: &PhysicalLens, aspect_ratio: Float) -> Self {
+        let mut camera = ProjectiveCamera::new_physical(&self.origin, &self.look_at, &self.up, lens, aspect_ratio, 0.0, 0.0);
+        camera.shutter = self.shutter.clone();
+
+        camera
+    }
+
+    // The camera moved around the point it looks at, by angle degrees around its up direction (counterclockwise seen
+    // from above). Everything else stays the same, for turntable animations.
+    pub fn orbit(&self, angle: Float) -> Self {
+        let axis = self.up.unit_vector();
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let rotate = |v: &Vec3<Float>| *v * cos + axis.cross(v) * sin + axis * (axis.dot(v) * (1.0 - cos));
+
+        let mut camera = self.clone();
+        camera.origin = self.look_at + rotate(&(self.origin - self.look_at));
+        camera.lower_left_corner = self.look_at + rotate(&(self.lower_left_corner - self.look_at));
+        camera.horizontal = rotate(&self.horizontal);
+        camera.vertical = rotate(&self.vertical);
+        camera.u = rotate(&self.u);
+        camera.v = rotate(&self.v);
+        camera.w = rotate(&self.w);
+
+        camera
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn aperture_diameter(&self) -> Float {
+        self.lens_radius * 2.0
+    }
+
+    pub fn focus_distance(&self) -> Float {
+        self.focus_dist
+    }
+
+    // Moves the focus plane, keeping the field of view. Orthographic cameras have no lens to focus.
+    pub fn set_focus_distance(&mut self, focus_dist: Float) {
+        if self.projection != Projection::Perspective || focus_dist <= 0.0 {
+            return;
+        }
+
+        let scale = focus_dist / self.focus_dist;
+        self.horizontal = self.horizontal * scale;
+        self.vertical = self.vertical * scale;
+        self.lower_left_corner = self.origin + (self.lower_left_corner - self.origin) * scale;
+        self.focus_dist = focus_dist;
+    }
+
+    // Focuses on whatever is seen through the image position (s, t), like a camera's focus point. Returns the new
+    // focus distance, or none when the ray hits nothing.
+    pub fn autofocus(&mut self, s: Float, t: Float, world: &World) -> Option<Float> {
+        let ray = Ray::<Float> {
+            orig: self.origin,
+            dir: self.lower_left_corner + self.horizontal*s + self.vertical*t - self.origin,
+            time: self.shutter.open,
+            wavelength: Option::None
+        };
+        let hit = world.objects.hit(&ray, 0.001, Float::MAX)?;
+        let focus_dist = (hit.point - self.origin).dot(&self.w.reverse_dir());
+
+        self.set_focus_distance(focus_dist);
+        Some(focus_dist)
+    }
+
+}
+
+impl Camera for ProjectiveCamera {
+    fn get_ray(&self, s: Float, t: Float, sampler: &mut dyn Sampler) -> Option<Ray<Float>> {
+        let rd = self.aperture.sample(sampler.get_2d()) * self.lens_radius;
+        let time = self.shutter.sample_time(t, sampler.get_1d());
+
+        match self.projection {
+            Projection::Perspective => {
+                let offset = self.u*rd.x() + self.v*rd.y();
+
+                Some(Ray::<Float> {
+                    orig: self.origin + offset,
+                    dir: self.lower_left_corner + self.horizontal*s + self.vertical*t - self.origin - offset,
+                    time,
+                    wavelength: Option::None
+                })
+            }
+            Projection::Orthographic => {
+                Some(Ray::<Float> {
+                    orig: self.lower_left_corner + self.horizontal*s + self.vertical*t,
+                    dir: self.w.reverse_dir(),
+                    time,
+                    wavelength: Option::None
+                })
+            }
+        }
+    }
+}
+// --------------------------------------------------------------------------------------------------------------------
+// Equirectangular (latitude-longitude) camera: the full sphere of directions around look_from, 360 degrees across
+// and 180 degrees down, with the view direction in the middle. Rendered from a camera looking down -z with +y up,
+// the image lines up with how environment maps are read, so it can be loaded back with --environment.
+
+pub struct EquirectangularCamera {
+    origin: Vec3<Float>,
+    u: Vec3<Float>,
+    v: Vec3<Float>,
+    w: Vec3<Float>,
+    pub shutter: Shutter
+}
+
+impl EquirectangularCamera {
+    pub fn new(look_from: &Vec3<Float>, look_at: &Vec3<Float>, up: &Vec3<Float>, time0: Float, time1: Float) -> Self {
+        let w = (*look_from - *look_at).unit_vector();
+        let u = up.cross(&w).unit_vector();
+        let v = w.cross(&u);
+
+        Self {
+            origin: *look_from,
+            u,
+            v,
+            w,
+            shutter: Shutter::new(time0, time1)
+        }
+    }
+}
+
+impl Camera for EquirectangularCamera {
+    fn get_ray(&self, s: Float, t: Float, sampler: &mut dyn Sampler) -> Option<Ray<Float>> {
+        let _lens = sampler.get_2d();
+        let time = self.shutter.sample_time(t, sampler.get_1d());
+
+        let pi = std::f32::consts::PI as Float;
+        let phi = (s - 0.5) * 2.0 * pi;
+        let elevation = (0.5 - t) * pi;
+        let (sin_phi, cos_phi) = phi.sin_cos();
+        let (sin_elevation, cos_elevation) = elevation.sin_cos();
+
+        Some(Ray::<Float> {
+            orig: self.origin,
+            dir: self.w.reverse_dir()*(cos_elevation*cos_phi) + self.u*(cos_elevation*sin_phi) + self.v*sin_elevation,
+            time,
+            wavelength: Option::None
+        })
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Fisheye camera: a circular image inscribed in the frame, with the view direction in the center and fov degrees
+// (up to 360) across the circle. Equidistant fisheyes keep the angle from the center proportional to the distance on
+// the image (the usual dome master format), equisolid ones keep areas proportional to solid angle, like most real
+// fisheye lenses.
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum FisheyeMapping {
+    Equidistant,
+    Equisolid
+}
+
+impl FisheyeMapping {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "equidistant" => { Some(FisheyeMapping::Equidistant) }
+            "equisolid" => { Some(FisheyeMapping::Equisolid) }
+            _ => { Option::None }
+        }
+    }
+}
+
+pub struct FisheyeCamera {
+    mapping: FisheyeMapping,
+    origin: Vec3<Float>,
+    u: Vec3<Float>,
+    v: Vec3<Float>,
+    w: Vec3<Float>,
+    half_fov: Float,
+    aspect_ratio: Float,
+    pub shutter: Shutter
+}
+
+impl FisheyeCamera {
+    pub fn new(look_from: &Vec3<Float>, look_at: &Vec3<Float>, up: &Vec3<Float>, mapping: FisheyeMapping, fov: Float,
+            aspect_ratio: Float, time0: Float, time1: Float) -> Self {
+        let w = (*look_from - *look_at).unit_vector();
+        let u = up.cross(&w).unit_vector();
+        let v = w.cross(&u);
+
+        Self {
+            mapping,
+            origin: *look_from,
+            u,
+            v,
+            w,
+            half_fov: fov.clamp(1.0, 360.0).to_radians() * 0.5,
+            aspect_ratio,
+            shutter: Shutter::new(time0, time1)
+        }
+    }
+}
+
+impl Camera for FisheyeCamera {
+    fn get_ray(&self, s: Float, t: Float, sampler: &mut dyn Sampler) -> Option<Ray<Float>> {
+        let _lens = sampler.get_2d();
+        let time = self.shutter.sample_time(t, sampler.get_1d());
+
+        // Position relative to the image circle, which fits the shorter side of the frame
+        let x = (s - 0.5) * self.aspect_ratio;
+        let y = 0.5 - t;
+        let r = (x*x + y*y).sqrt() / (0.5 * Float::min(self.aspect_ratio, 1.0));
+        if r > 1.0 {
+            return Option::None;
+        }
+
+        // Angle away from the view direction
+        let theta = match self.mapping {
+            FisheyeMapping::Equidistant => { r * self.half_fov }
+            FisheyeMapping::Equisolid => { 2.0 * (r * (self.half_fov * 0.5).sin()).asin() }
+        };
+        let phi = Float::atan2(y, x);
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let (sin_phi, cos_phi) = phi.sin_cos();
+
+        Some(Ray::<Float> {
+            orig: self.origin,
+            dir: self.w.reverse_dir()*cos_theta + (self.u*cos_phi + self.v*sin_phi)*sin_theta,
+            time,
+            wavelength: Option::None
+        })
+    }
+}
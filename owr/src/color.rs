@@ -45,3 +45,94 @@ pub fn print_color(color: &Color) {
     log_print!("{} {} {}\n", color[0], color[1], color[2]);
 }
 
+
+// --------------------------------------------------------------------------------------------------------------------
+// Physical colors
+
+// CIE XYZ to linear sRGB (D65 white), can go negative for colors outside the sRGB gamut
+pub fn xyz_to_rgb(x: Float, y: Float, z: Float) -> Vec3<Float> {
+    Vec3::new(
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z
+    )
+}
+
+// CIE 1931 color matching functions, the multi-lobe gaussian fit of Wyman, Sloan & Shirley 2013 (wavelength in nm)
+fn cie_xyz(lambda: f64) -> (f64, f64, f64) {
+    let g = |mu: f64, sigma1: f64, sigma2: f64| {
+        let t = (lambda - mu) / if lambda < mu { sigma1 } else { sigma2 };
+        (-0.5 * t * t).exp()
+    };
+
+    (
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8)
+    )
+}
+
+// Linear sRGB color of a black body at the given temperature (Kelvin), scaled to a luminance of 1.
+// Candle light is around 1900K, incandescent bulbs 2700K, daylight 5500-6500K.
+pub fn blackbody(kelvin: Float) -> Vec3<Float> {
+    // Planck's law, constants in SI units
+    let (h, c, k) = (6.62607015e-34, 2.99792458e8, 1.380649e-23);
+    let temperature = Float::max(kelvin, 1.0) as f64;
+
+    let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+    for lambda in (360..=830).step_by(5) {
+        let lambda = lambda as f64;
+        let meters = lambda * 1e-9;
+        let radiance = 2.0 * h * c * c / (meters.powi(5) * ((h * c / (meters * k * temperature)).exp() - 1.0));
+        let (cx, cy, cz) = cie_xyz(lambda);
+        x += radiance * cx;
+        y += radiance * cy;
+        z += radiance * cz;
+    }
+
+    if y <= 0.0 {
+        return Vec3::default();
+    }
+
+    let rgb = xyz_to_rgb((x / y) as Float, 1.0, (z / y) as Float);
+    let rgb = Vec3::new(Float::max(rgb[0], 0.0), Float::max(rgb[1], 0.0), Float::max(rgb[2], 0.0));
+
+    // Clipping out of gamut colors changes the luminance a little, put it back to 1
+    let luminance = 0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2];
+    rgb * (1.0 / luminance)
+}
+
+// Visible wavelengths (nm) that dispersive materials pick from
+pub const WAVELENGTH_MIN: Float = 380.0;
+pub const WAVELENGTH_MAX: Float = 720.0;
+
+// Linear sRGB of light of a single wavelength, out of gamut parts clipped
+fn wavelength_rgb(lambda: f64) -> (f64, f64, f64) {
+    let (x, y, z) = cie_xyz(lambda);
+    let rgb = xyz_to_rgb(x as Float, y as Float, z as Float);
+    (Float::max(rgb[0], 0.0) as f64, Float::max(rgb[1], 0.0) as f64, Float::max(rgb[2], 0.0) as f64)
+}
+
+thread_local! {
+    // Average of wavelength_rgb over the visible range, in 1nm steps
+    static WAVELENGTH_RGB_AVERAGE: (f64, f64, f64) = {
+        let (mut r, mut g, mut b) = (0.0, 0.0, 0.0);
+        let steps = (WAVELENGTH_MAX - WAVELENGTH_MIN) as u32;
+        for i in 0..steps {
+            let (cr, cg, cb) = wavelength_rgb(WAVELENGTH_MIN as f64 + i as f64 + 0.5);
+            r += cr;
+            g += cg;
+            b += cb;
+        }
+        (r / steps as f64, g / steps as f64, b / steps as f64)
+    };
+}
+
+// Color a path that picked this wavelength (uniformly over the visible range) carries, so that all wavelengths
+// together add up to white
+pub fn wavelength_weight(lambda: Float) -> Vec3<Float> {
+    let (r, g, b) = wavelength_rgb(lambda as f64);
+    WAVELENGTH_RGB_AVERAGE.with(|average| {
+        Vec3::new((r / average.0) as Float, (g / average.1) as Float, (b / average.2) as Float)
+    })
+}
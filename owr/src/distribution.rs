@@ -0,0 +1,108 @@
+use crate::types::*;
+
+// --------------------------------------------------------------------------------------------------------------------
+// Piecewise constant 1D distribution over [0, 1), sampled by inverting its CDF
+
+pub struct Distribution1D {
+    func: Vec<Float>,
+    cdf: Vec<Float>,
+    func_integral: Float
+}
+
+impl Distribution1D {
+    pub fn new(func: &[Float]) -> Self {
+        let n = func.len();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 1..(n + 1) {
+            cdf[i] = cdf[i - 1] + func[i - 1].abs() / (n as Float);
+        }
+
+        // All zero: fall back to uniform
+        let func_integral = cdf[n];
+        for (i, value) in cdf.iter_mut().enumerate().skip(1) {
+            *value = if func_integral == 0.0 { (i as Float) / (n as Float) } else { *value / func_integral };
+        }
+
+        Self {
+            func: func.iter().map(|f| f.abs()).collect(),
+            cdf,
+            func_integral
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    pub fn integral(&self) -> Float {
+        self.func_integral
+    }
+
+    // Returns the sampled position in [0, 1), its pdf and the index of the piece it fell in
+    pub fn sample_continuous(&self, u: Float) -> (Float, Float, usize) {
+        // Last cdf entry that is <= u
+        let offset = usize::min(self.cdf.partition_point(|&value| value <= u).saturating_sub(1), self.count() - 1);
+
+        let mut du = u - self.cdf[offset];
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        if width > 0.0 {
+            du /= width;
+        }
+
+        let pdf = if self.func_integral > 0.0 { self.func[offset] / self.func_integral } else { 1.0 };
+        let x = Float::min(((offset as Float) + du) / (self.count() as Float), 1.0 - Float::EPSILON);
+
+        (x, pdf, offset)
+    }
+
+    pub fn pdf(&self, x: Float) -> Float {
+        if self.func_integral == 0.0 {
+            return 1.0;
+        }
+
+        let offset = usize::min((x * (self.count() as Float)) as usize, self.count() - 1);
+        self.func[offset] / self.func_integral
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Piecewise constant 2D distribution over [0, 1)^2: a marginal distribution picks the row,
+// then that row's conditional distribution picks the column
+
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D
+}
+
+impl Distribution2D {
+    // func is row major, width x height
+    pub fn new(func: &[Float], width: usize, height: usize) -> Self {
+        let conditional: Vec<Distribution1D> = (0..height).map(|y| Distribution1D::new(&func[(y * width)..((y + 1) * width)])).collect();
+        let row_integrals: Vec<Float> = conditional.iter().map(|row| row.integral()).collect();
+
+        Self {
+            conditional,
+            marginal: Distribution1D::new(&row_integrals)
+        }
+    }
+
+    // Returns ((x, y), pdf) with the pdf relative to area in [0, 1)^2
+    pub fn sample_continuous(&self, u: (Float, Float)) -> ((Float, Float), Float) {
+        let (y, pdf_y, row) = self.marginal.sample_continuous(u.1);
+        let (x, pdf_x, _) = self.conditional[row].sample_continuous(u.0);
+
+        ((x, y), pdf_x * pdf_y)
+    }
+
+    pub fn pdf(&self, x: Float, y: Float) -> Float {
+        let row = usize::min((y * (self.conditional.len() as Float)) as usize, self.conditional.len() - 1);
+        let row_distribution = &self.conditional[row];
+        let column = usize::min((x * (row_distribution.count() as Float)) as usize, row_distribution.count() - 1);
+
+        if self.marginal.integral() == 0.0 {
+            return 1.0;
+        }
+
+        row_distribution.func[column] / self.marginal.integral()
+    }
+}
@@ -0,0 +1,165 @@
+use crate::types::*;
+use crate::vec3::Vec3;
+use crate::light::{self, Light, LightSample};
+use crate::distribution::Distribution2D;
+
+extern crate image;
+
+// --------------------------------------------------------------------------------------------------------------------
+// Environment light: an equirectangular (lat-long) image around the scene, the image center looks down -z.
+// Directions are importance sampled by luminance, so small bright features like the sun get found by shadow rays.
+
+pub struct EnvironmentLight {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vec3<Float>>,
+    rotation: Float,
+    intensity: Float,
+    distribution: Distribution2D
+}
+
+impl EnvironmentLight {
+    // Pixels are linear radiance, row major from the top; rotation (degrees) turns the map around +y
+    pub fn new(width: usize, height: usize, pixels: Vec<Vec3<Float>>, rotation: Float, intensity: Float) -> Self {
+        // Rows near the poles cover less solid angle, weight them down by sin(theta)
+        let pi = std::f32::consts::PI as Float;
+        let mut func = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let sin_theta = Float::sin(pi * ((y as Float) + 0.5) / (height as Float));
+            for x in 0..width {
+                func.push(light::luminance(&pixels[y * width + x]) * sin_theta);
+            }
+        }
+
+        Self {
+            width,
+            height,
+            pixels,
+            rotation: rotation.to_radians(),
+            intensity,
+            distribution: Distribution2D::new(&func, width, height)
+        }
+    }
+
+    // Loads .hdr (radiance) and, with the openexr feature, .exr files. Anything else goes through
+    // the image crate as an 8-bit image and is linearized with the same gamma of 2 the output uses.
+    pub fn load(filename: &str, rotation: Float, intensity: Float) -> Result<Self, String> {
+        let extension = std::path::Path::new(filename).extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+
+        let (width, height, pixels) = match extension.as_str() {
+            "hdr" => {
+                let file = std::fs::File::open(filename).map_err(|e| e.to_string())?;
+                let decoder = image::codecs::hdr::HdrDecoder::new(std::io::BufReader::new(file)).map_err(|e| e.to_string())?;
+                let metadata = decoder.metadata();
+                let pixels = decoder.read_image_hdr().map_err(|e| e.to_string())?;
+
+                (metadata.width as usize, metadata.height as usize, pixels.iter().map(|p| Vec3::new(p[0] as Float, p[1] as Float, p[2] as Float)).collect())
+            }
+            #[cfg(feature = "openexr")]
+            "exr" => {
+                let image = exr::prelude::read_first_rgba_layer_from_file(
+                    filename,
+                    |resolution, _| {
+                        (resolution.width(), vec![Vec3::default(); resolution.width() * resolution.height()])
+                    },
+                    |(width, pixels): &mut (usize, Vec<Vec3<Float>>), position, (r, g, b, _a): (f32, f32, f32, f32)| {
+                        pixels[position.y() * *width + position.x()] = Vec3::new(r as Float, g as Float, b as Float);
+                    }
+                ).map_err(|e| e.to_string())?;
+
+                let (width, pixels) = image.layer_data.channel_data.pixels;
+                (width, pixels.len() / usize::max(width, 1), pixels)
+            }
+            _ => {
+                let image = image::open(filename).map_err(|e| e.to_string())?.to_rgb8();
+                let pixels = image.pixels().map(|p| {
+                    let linear = |c: u8| { let c = (c as Float) / 255.0; c * c };
+                    Vec3::new(linear(p[0]), linear(p[1]), linear(p[2]))
+                }).collect();
+
+                (image.width() as usize, image.height() as usize, pixels)
+            }
+        };
+
+        if width == 0 || height == 0 {
+            return Err(format!("Environment map '{}' is empty", filename));
+        }
+
+        Ok(EnvironmentLight::new(width, height, pixels, rotation, intensity))
+    }
+
+    // Radiance arriving from the given direction
+    pub fn radiance(&self, direction: &Vec3<Float>) -> Vec3<Float> {
+        let (u, v) = self.direction_to_uv(&direction.unit_vector());
+        self.lookup(u, v)
+    }
+
+    fn lookup(&self, u: Float, v: Float) -> Vec3<Float> {
+        let x = usize::min((u * (self.width as Float)) as usize, self.width - 1);
+        let y = usize::min((v * (self.height as Float)) as usize, self.height - 1);
+
+        self.pixels[y * self.width + x] * self.intensity
+    }
+
+    fn direction_to_uv(&self, direction: &Vec3<Float>) -> (Float, Float) {
+        let pi = std::f32::consts::PI as Float;
+        let (sin_r, cos_r) = self.rotation.sin_cos();
+        let x = direction.x() * cos_r - direction.z() * sin_r;
+        let z = direction.x() * sin_r + direction.z() * cos_r;
+
+        let phi = Float::atan2(x, -z);
+        let theta = Float::acos(direction.y().clamp(-1.0, 1.0));
+        let u = 0.5 + phi / (2.0 * pi);
+
+        (if u >= 1.0 { u - 1.0 } else { u }, theta / pi)
+    }
+
+    fn uv_to_direction(&self, u: Float, v: Float) -> Vec3<Float> {
+        let pi = std::f32::consts::PI as Float;
+        let phi = (u - 0.5) * 2.0 * pi;
+        let theta = v * pi;
+        let x = theta.sin() * phi.sin();
+        let z = -theta.sin() * phi.cos();
+
+        let (sin_r, cos_r) = self.rotation.sin_cos();
+        Vec3::new(x * cos_r + z * sin_r, theta.cos(), -x * sin_r + z * cos_r)
+    }
+}
+
+impl Light for EnvironmentLight {
+    fn sample(&self, _point: &Vec3<Float>, u: (Float, Float)) -> Option<LightSample> {
+        let ((map_u, map_v), map_pdf) = self.distribution.sample_continuous(u);
+        if map_pdf == 0.0 {
+            return Option::None;
+        }
+
+        // Area in the map to solid angle: d(omega) = 2 pi^2 sin(theta) du dv
+        let pi = std::f32::consts::PI as Float;
+        let sin_theta = Float::sin(map_v * pi);
+        if sin_theta <= 0.0 {
+            return Option::None;
+        }
+
+        Some(LightSample {
+            direction: self.uv_to_direction(map_u, map_v),
+            distance: Float::INFINITY,
+            radiance: self.lookup(map_u, map_v),
+            pdf: map_pdf / (2.0 * pi * pi * sin_theta),
+            is_delta: false
+        })
+    }
+
+    fn pdf(&self, _point: &Vec3<Float>, direction: &Vec3<Float>) -> Float {
+        let pi = std::f32::consts::PI as Float;
+        let (u, v) = self.direction_to_uv(&direction.unit_vector());
+        let sin_theta = Float::sin(v * pi);
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+
+        self.distribution.pdf(u, v) / (2.0 * pi * pi * sin_theta)
+    }
+}
+
+unsafe impl Sync for EnvironmentLight {}
+unsafe impl Send for EnvironmentLight {}
@@ -3,7 +3,12 @@ use std::io::Write;
 use std::sync::Arc;
 use crate::{log_print, hittable};
 use crate::vec3::Vec3;
-use crate::hittable::{Hittable, HittableList};
+use crate::hittable::HittableList;
+use crate::scene::{Scene, World};
+use crate::background::{ConstantBackground, GradientBackground};
+use crate::animation::Interpolation;
+use crate::light::{Light, PointLight, SpotLight, DirectionalLight, GoniometricLight};
+use crate::ies::IesProfile;
 use crate::sphere::{Sphere, MovingSphere};
 use crate::utils;
 use crate::sampling::{render_image};
@@ -12,15 +17,17 @@ use crate::types::*;
 use crate::camera;
 use crate::texture;
 use crate::bvh;
+use crate::filter::PixelFilter;
+use crate::sampler::SamplerType;
 
 extern crate image;
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn run_and_print_ppm(params: &RaytracerParams, camera: &camera::Camera, world: &Arc<dyn Hittable>) {
+pub fn run_and_print_ppm(params: &RaytracerParams, camera: &dyn camera::Camera, world: &World) {
     log_print!("P3\n{0} {1}\n255\n", params.image_width, params.image_height);
 
-    let results = render_image(true, true, &params, &camera, world).unwrap().into_raw();
+    let results = render_image(true, true, &params, camera, world).unwrap().into_raw();
     let mut count = 0;
     for &color in &results {
         count = count + 1;
@@ -36,7 +43,7 @@ pub fn run_and_print_ppm(params: &RaytracerParams, camera: &camera::Camera, worl
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn scene_select(scene_num : u32, image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32, image: image::RgbaImage) -> (RaytracerParams, camera::Camera, HittableList) {
+pub fn scene_select(scene_num : u32, image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32, image: image::RgbaImage) -> (RaytracerParams, camera::ProjectiveCamera, Scene) {
     match scene_num {
         0 => { first_weekend_example(image_width, image_height, samples_per_pixel, max_depth) }
         1 => { second_weekend_example_4dot4(image_width, image_height, samples_per_pixel, max_depth) }
@@ -48,13 +55,20 @@ pub fn scene_select(scene_num : u32, image_width: u32, image_height: u32, sample
         7 => { second_weekend_example_8dot2(image_width, image_height, samples_per_pixel, max_depth) }
         8 => { second_weekend_example_9dot1(image_width, image_height, samples_per_pixel, max_depth) }
         9 => { second_weekend_final_scene(image_width, image_height, samples_per_pixel, max_depth, image) }
+        10 => { lights_example(image_width, image_height, samples_per_pixel, max_depth) }
+        11 => { ies_lights_example(image_width, image_height, samples_per_pixel, max_depth) }
+        12 => { motion_blur_example(image_width, image_height, samples_per_pixel, max_depth) }
+        13 => { materials_example(image_width, image_height, samples_per_pixel, max_depth) }
+        14 => { metals_example(image_width, image_height, samples_per_pixel, max_depth) }
+        15 => { glass_example(image_width, image_height, samples_per_pixel, max_depth) }
+        16 => { coated_example(image_width, image_height, samples_per_pixel, max_depth) }
         _ => { first_weekend_example(image_width, image_height, samples_per_pixel, max_depth) }
     }
 }
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn first_weekend_example(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::Camera, HittableList) {
+pub fn first_weekend_example(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::ProjectiveCamera, Scene) {
 
     fn example_params(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> RaytracerParams {
         RaytracerParams {
@@ -63,10 +77,14 @@ pub fn first_weekend_example(image_width: u32, image_height: u32, samples_per_pi
             image_height,
             samples_per_pixel,
             max_depth,
+            filter: PixelFilter::default(),
+            sampler: SamplerType::default(),
+            seed: 0,
+            next_event_estimation: true,
         }
     }
 
-    pub fn example_camera(aspect_ratio: Float) -> camera::Camera {
+    pub fn example_camera(aspect_ratio: Float) -> camera::ProjectiveCamera {
         let camera;
         {
             let look_from = Vec3::new(13.0, 2.0, 3.0);
@@ -75,7 +93,7 @@ pub fn first_weekend_example(image_width: u32, image_height: u32, samples_per_pi
             let focus_dist = 10.0;
             let aperture = 0.1;
     
-            camera = camera::Camera::new(
+            camera = camera::ProjectiveCamera::new(
                 &look_from,
                 &look_at,
                 &up,
@@ -84,8 +102,7 @@ pub fn first_weekend_example(image_width: u32, image_height: u32, samples_per_pi
                 aperture,
                 focus_dist,
                 0.0,
-                1.0,
-                Vec3::new(0.70, 0.80, 1.00)
+                1.0
             );
         }
     
@@ -121,7 +138,7 @@ pub fn first_weekend_example(image_width: u32, image_height: u32, samples_per_pi
                         world.list.push(Arc::new(Sphere { center, radius, material }));
                     } else {
                         // Glass
-                        let material = Arc::new(material::Dielectric { index_of_refraction: 1.5 });
+                        let material = Arc::new(material::Dielectric::new(1.5));
                         world.list.push(Arc::new(Sphere { center, radius, material }));
                     }
                 }
@@ -129,7 +146,7 @@ pub fn first_weekend_example(image_width: u32, image_height: u32, samples_per_pi
         }
 
         {
-            let material1 = Arc::new(material::Dielectric { index_of_refraction: 1.5 });
+            let material1 = Arc::new(material::Dielectric::new(1.5));
             world.list.push(Arc::new(Sphere { center: Vec3::new(0.0, 1.0, 0.0), radius: 1.0, material: material1 }));
         }
 
@@ -148,12 +165,12 @@ pub fn first_weekend_example(image_width: u32, image_height: u32, samples_per_pi
 
     // Return
     let params = example_params(image_width, image_height, samples_per_pixel, max_depth);
-    (params, example_camera(params.aspect_ratio), example_scene())
+    (params, example_camera(params.aspect_ratio), Scene::new(example_scene(), Arc::new(GradientBackground::default())))
 }
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn second_weekend_example_4dot4(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::Camera, HittableList)  {
+pub fn second_weekend_example_4dot4(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::ProjectiveCamera, Scene)  {
     fn example_params(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> RaytracerParams {
         RaytracerParams {
             aspect_ratio: (image_width as Float) / (image_height as Float),
@@ -161,10 +178,14 @@ pub fn second_weekend_example_4dot4(image_width: u32, image_height: u32, samples
             image_height,
             samples_per_pixel,
             max_depth,
+            filter: PixelFilter::default(),
+            sampler: SamplerType::default(),
+            seed: 0,
+            next_event_estimation: true,
         }
     }
 
-    pub fn example_camera(aspect_ratio: Float) -> camera::Camera {
+    pub fn example_camera(aspect_ratio: Float) -> camera::ProjectiveCamera {
         let camera;
         {
             let look_from = Vec3::new(13.0, 2.0, 3.0);
@@ -173,7 +194,7 @@ pub fn second_weekend_example_4dot4(image_width: u32, image_height: u32, samples
             let focus_dist = 10.0;
             let aperture = 0.0;
     
-            camera = camera::Camera::new(
+            camera = camera::ProjectiveCamera::new(
                 &look_from,
                 &look_at,
                 &up,
@@ -182,8 +203,7 @@ pub fn second_weekend_example_4dot4(image_width: u32, image_height: u32, samples
                 aperture,
                 focus_dist,
                 0.0,
-                1.0,
-                Vec3::new(0.70, 0.80, 1.00)
+                1.0
             );
         }
     
@@ -203,12 +223,12 @@ pub fn second_weekend_example_4dot4(image_width: u32, image_height: u32, samples
 
     // Return
     let params = example_params(image_width, image_height, samples_per_pixel, max_depth);
-    (params, example_camera(params.aspect_ratio), example_scene())
+    (params, example_camera(params.aspect_ratio), Scene::new(example_scene(), Arc::new(ConstantBackground::new(&Vec3::new(0.70, 0.80, 1.00)))))
 }
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn second_weekend_example_5dot1(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::Camera, HittableList)  {
+pub fn second_weekend_example_5dot1(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::ProjectiveCamera, Scene)  {
     fn example_params(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> RaytracerParams {
         RaytracerParams {
             aspect_ratio: (image_width as Float) / (image_height as Float),
@@ -216,10 +236,14 @@ pub fn second_weekend_example_5dot1(image_width: u32, image_height: u32, samples
             image_height,
             samples_per_pixel,
             max_depth,
+            filter: PixelFilter::default(),
+            sampler: SamplerType::default(),
+            seed: 0,
+            next_event_estimation: true,
         }
     }
 
-    pub fn example_camera(aspect_ratio: Float) -> camera::Camera {
+    pub fn example_camera(aspect_ratio: Float) -> camera::ProjectiveCamera {
         let camera;
         {
             let look_from = Vec3::new(13.0, 2.0, 3.0);
@@ -228,7 +252,7 @@ pub fn second_weekend_example_5dot1(image_width: u32, image_height: u32, samples
             let focus_dist = 10.0;
             let aperture = 0.0;
     
-            camera = camera::Camera::new(
+            camera = camera::ProjectiveCamera::new(
                 &look_from,
                 &look_at,
                 &up,
@@ -237,8 +261,7 @@ pub fn second_weekend_example_5dot1(image_width: u32, image_height: u32, samples
                 aperture,
                 focus_dist,
                 0.0,
-                1.0,
-                Vec3::new(0.70, 0.80, 1.00)
+                1.0
             );
         }
     
@@ -258,12 +281,12 @@ pub fn second_weekend_example_5dot1(image_width: u32, image_height: u32, samples
 
     // Return
     let params = example_params(image_width, image_height, samples_per_pixel, max_depth);
-    (params, example_camera(params.aspect_ratio), example_scene())
+    (params, example_camera(params.aspect_ratio), Scene::new(example_scene(), Arc::new(ConstantBackground::new(&Vec3::new(0.70, 0.80, 1.00)))))
 }
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn second_weekend_example_6dot2(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32, earth_image: image::RgbaImage) -> (RaytracerParams, camera::Camera, HittableList)  {
+pub fn second_weekend_example_6dot2(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32, earth_image: image::RgbaImage) -> (RaytracerParams, camera::ProjectiveCamera, Scene)  {
     fn example_params(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> RaytracerParams {
         RaytracerParams {
             aspect_ratio: (image_width as Float) / (image_height as Float),
@@ -271,10 +294,14 @@ pub fn second_weekend_example_6dot2(image_width: u32, image_height: u32, samples
             image_height,
             samples_per_pixel,
             max_depth,
+            filter: PixelFilter::default(),
+            sampler: SamplerType::default(),
+            seed: 0,
+            next_event_estimation: true,
         }
     }
 
-    pub fn example_camera(aspect_ratio: Float) -> camera::Camera {
+    pub fn example_camera(aspect_ratio: Float) -> camera::ProjectiveCamera {
         let camera;
         {
             let look_from = Vec3::new(13.0, 2.0, 3.0);
@@ -283,7 +310,7 @@ pub fn second_weekend_example_6dot2(image_width: u32, image_height: u32, samples
             let focus_dist = 10.0;
             let aperture = 0.0;
     
-            camera = camera::Camera::new(
+            camera = camera::ProjectiveCamera::new(
                 &look_from,
                 &look_at,
                 &up,
@@ -292,8 +319,7 @@ pub fn second_weekend_example_6dot2(image_width: u32, image_height: u32, samples
                 aperture,
                 focus_dist,
                 0.0,
-                1.0,
-                Vec3::new(0.70, 0.80, 1.00)
+                1.0
             );
         }
     
@@ -312,12 +338,12 @@ pub fn second_weekend_example_6dot2(image_width: u32, image_height: u32, samples
 
     // Return
     let params = example_params(image_width, image_height, samples_per_pixel, max_depth);
-    (params, example_camera(params.aspect_ratio), example_scene(earth_image))
+    (params, example_camera(params.aspect_ratio), Scene::new(example_scene(earth_image), Arc::new(ConstantBackground::new(&Vec3::new(0.70, 0.80, 1.00)))))
 }
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn second_weekend_example_7dot4(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::Camera, HittableList)  {
+pub fn second_weekend_example_7dot4(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::ProjectiveCamera, Scene)  {
     fn example_params(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> RaytracerParams {
         RaytracerParams {
             aspect_ratio: (image_width as Float) / (image_height as Float),
@@ -325,10 +351,14 @@ pub fn second_weekend_example_7dot4(image_width: u32, image_height: u32, samples
             image_height,
             samples_per_pixel,
             max_depth,
+            filter: PixelFilter::default(),
+            sampler: SamplerType::default(),
+            seed: 0,
+            next_event_estimation: true,
         }
     }
 
-    pub fn example_camera(aspect_ratio: Float) -> camera::Camera {
+    pub fn example_camera(aspect_ratio: Float) -> camera::ProjectiveCamera {
         let camera;
         {
             let look_from = Vec3::new(13.0, 2.0, 3.0);
@@ -337,7 +367,7 @@ pub fn second_weekend_example_7dot4(image_width: u32, image_height: u32, samples
             let focus_dist = 10.0;
             let aperture = 0.0;
     
-            camera = camera::Camera::new(
+            camera = camera::ProjectiveCamera::new(
                 &look_from,
                 &look_at,
                 &up,
@@ -346,8 +376,7 @@ pub fn second_weekend_example_7dot4(image_width: u32, image_height: u32, samples
                 aperture,
                 focus_dist,
                 0.0,
-                1.0,
-                Vec3::default()
+                1.0
             );
         }
     
@@ -371,12 +400,12 @@ pub fn second_weekend_example_7dot4(image_width: u32, image_height: u32, samples
 
     // Return
     let params = example_params(image_width, image_height, samples_per_pixel, max_depth);
-    (params, example_camera(params.aspect_ratio), example_scene())
+    (params, example_camera(params.aspect_ratio), Scene::new(example_scene(), Arc::new(ConstantBackground::new(&Vec3::default()))))
 }
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn second_weekend_example_7dot6(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::Camera, HittableList)  {
+pub fn second_weekend_example_7dot6(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::ProjectiveCamera, Scene)  {
     fn example_params(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> RaytracerParams {
         RaytracerParams {
             aspect_ratio: (image_width as Float) / (image_height as Float),
@@ -384,10 +413,14 @@ pub fn second_weekend_example_7dot6(image_width: u32, image_height: u32, samples
             image_height,
             samples_per_pixel,
             max_depth,
+            filter: PixelFilter::default(),
+            sampler: SamplerType::default(),
+            seed: 0,
+            next_event_estimation: true,
         }
     }
 
-    pub fn example_camera(aspect_ratio: Float) -> camera::Camera {
+    pub fn example_camera(aspect_ratio: Float) -> camera::ProjectiveCamera {
         let camera;
         {
             let look_from = Vec3::new(278.0, 278.0, -1250.0);
@@ -396,7 +429,7 @@ pub fn second_weekend_example_7dot6(image_width: u32, image_height: u32, samples
             let focus_dist = 10.0;
             let aperture = 0.0;
     
-            camera = camera::Camera::new(
+            camera = camera::ProjectiveCamera::new(
                 &look_from,
                 &look_at,
                 &up,
@@ -405,8 +438,7 @@ pub fn second_weekend_example_7dot6(image_width: u32, image_height: u32, samples
                 aperture,
                 focus_dist,
                 0.0,
-                1.0,
-                Vec3::default()
+                1.0
             );
         }
     
@@ -428,9 +460,12 @@ pub fn second_weekend_example_7dot6(image_width: u32, image_height: u32, samples
             Arc::new(material::Lambertian::new(
                 Arc::new(texture::SolidColor::new(&Vec3::new(0.12, 0.45, 0.15)))));
 
-        let diff_light_material = 
-            Arc::new(material::DiffuseLight::new(
-           Arc::new(texture::SolidColor::new(&Vec3::new(4.0, 4.0, 4.0)))));
+        // Ceiling light, only shining down into the box (the rect's normal points up)
+        let mut diff_light =
+            material::DiffuseLight::new(
+           Arc::new(texture::SolidColor::new(&Vec3::new(4.0, 4.0, 4.0))));
+        diff_light.sides = material::EmitSides::Back;
+        let diff_light_material = Arc::new(diff_light);
 
         world.list.push(Arc::new(
             hittable::YZRect::new(0.0, 555.0, 0.0, 555.0, 555.0, green_material.clone())));
@@ -450,21 +485,21 @@ pub fn second_weekend_example_7dot6(image_width: u32, image_height: u32, samples
 
     // Return
     let params = example_params(image_width, image_height, samples_per_pixel, max_depth);
-    (params, example_camera(params.aspect_ratio), example_scene())
+    (params, example_camera(params.aspect_ratio), Scene::new(example_scene(), Arc::new(ConstantBackground::new(&Vec3::default()))))
 }
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn second_weekend_example_8dot0(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::Camera, HittableList)  {
+pub fn second_weekend_example_8dot0(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::ProjectiveCamera, Scene)  {
     let mut ret = second_weekend_example_7dot6(image_width, image_height, samples_per_pixel, max_depth);
 
     let white_material = 
         Arc::new(material::Lambertian::new(
         Arc::new(texture::SolidColor::new(&Vec3::new(0.73, 0.73, 0.73)))));
 
-    ret.2.list.push(
+    ret.2.objects.list.push(
         Arc::new(hittable::Box::new(&Vec3::new(130.0, 0.0, 65.0), &Vec3::new(295.0, 165.0, 230.0), white_material.clone())));
-    ret.2.list.push(
+    ret.2.objects.list.push(
         Arc::new(hittable::Box::new(&Vec3::new(265.0, 0.0, 295.0), &Vec3::new(430.0, 330.0, 460.0), white_material.clone())));
 
     ret
@@ -472,7 +507,7 @@ pub fn second_weekend_example_8dot0(image_width: u32, image_height: u32, samples
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn second_weekend_example_8dot2(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::Camera, HittableList)  {
+pub fn second_weekend_example_8dot2(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::ProjectiveCamera, Scene)  {
     let mut ret = second_weekend_example_7dot6(image_width, image_height, samples_per_pixel, max_depth);
 
     let white_material = 
@@ -483,20 +518,20 @@ pub fn second_weekend_example_8dot2(image_width: u32, image_height: u32, samples
         Arc::new(hittable::Box::new(&Vec3::new(0.0, 0.0, 0.0), &Vec3::new(165.0, 330.0, 165.0), white_material.clone()));
     let box1_rotated = Arc::new(hittable::RotateY::new(box1, 15.0));
     let box1_rotated_and_translated = Arc::new(hittable::Translate::new(box1_rotated, Vec3::new(265.0, 0.0, 295.0)));
-    ret.2.list.push(box1_rotated_and_translated);
+    ret.2.objects.list.push(box1_rotated_and_translated);
 
     let box2 =
         Arc::new(hittable::Box::new(&Vec3::new(0.0, 0.0, 0.0), &Vec3::new(165.0, 165.0, 165.0), white_material.clone()));
     let box2_rotated = Arc::new(hittable::RotateY::new(box2, -18.0));
     let box2_rotated_and_translated = Arc::new(hittable::Translate::new(box2_rotated, Vec3::new(130.0, 0.0, 65.0)));
-    ret.2.list.push(box2_rotated_and_translated);
+    ret.2.objects.list.push(box2_rotated_and_translated);
 
     ret
 }
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn second_weekend_example_9dot1(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::Camera, HittableList)  {
+pub fn second_weekend_example_9dot1(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::ProjectiveCamera, Scene)  {
     let mut ret = second_weekend_example_7dot6(image_width, image_height, samples_per_pixel, max_depth);
 
     let white_material = 
@@ -513,9 +548,9 @@ pub fn second_weekend_example_9dot1(image_width: u32, image_height: u32, samples
     let box2_rotated = Arc::new(hittable::RotateY::new(box2, -18.0));
     let box2_rotated_and_translated = Arc::new(hittable::Translate::new(box2_rotated, Vec3::new(130.0, 0.0, 65.0)));
     
-    ret.2.list.push(Arc::new (
+    ret.2.objects.list.push(Arc::new (
         hittable::ConstantMedium::new_with_constant_color(box1_rotated_and_translated, 0.01, &Vec3::new(0.0, 0.0, 0.0))));
-    ret.2.list.push(Arc::new (
+    ret.2.objects.list.push(Arc::new (
         hittable::ConstantMedium::new_with_constant_color(box2_rotated_and_translated, 0.01, &Vec3::new(1.0, 1.0, 1.0))));
 
     ret
@@ -523,7 +558,7 @@ pub fn second_weekend_example_9dot1(image_width: u32, image_height: u32, samples
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn second_weekend_final_scene(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32, earth_image: image::RgbaImage) -> (RaytracerParams, camera::Camera, HittableList)  {
+pub fn second_weekend_final_scene(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32, earth_image: image::RgbaImage) -> (RaytracerParams, camera::ProjectiveCamera, Scene)  {
     fn example_params(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> RaytracerParams {
         RaytracerParams {
             aspect_ratio: (image_width as Float) / (image_height as Float),
@@ -531,10 +566,14 @@ pub fn second_weekend_final_scene(image_width: u32, image_height: u32, samples_p
             image_height,
             samples_per_pixel,
             max_depth,
+            filter: PixelFilter::default(),
+            sampler: SamplerType::default(),
+            seed: 0,
+            next_event_estimation: true,
         }
     }
 
-    pub fn example_camera(aspect_ratio: Float) -> camera::Camera {
+    pub fn example_camera(aspect_ratio: Float) -> camera::ProjectiveCamera {
         let camera;
         {
             let look_from = Vec3::new(478.0, 278.0, -600.0);
@@ -543,7 +582,7 @@ pub fn second_weekend_final_scene(image_width: u32, image_height: u32, samples_p
             let focus_dist = 10.0;
             let aperture = 0.0;
     
-            camera = camera::Camera::new(
+            camera = camera::ProjectiveCamera::new(
                 &look_from,
                 &look_at,
                 &up,
@@ -552,8 +591,7 @@ pub fn second_weekend_final_scene(image_width: u32, image_height: u32, samples_p
                 aperture,
                 focus_dist,
                 0.0,
-                1.0,
-                Vec3::default()
+                1.0
             );
         }
     
@@ -586,10 +624,12 @@ pub fn second_weekend_final_scene(image_width: u32, image_height: u32, samples_p
         // Add ground boxes with a bvh wrapper
         world.list.push(bvh::BvhNode::build_bvh(&boxes1, 0.0, 1.0));
 
-        // Light
-        let diff_light_material = 
-            Arc::new(material::DiffuseLight::new(
-                Arc::new(texture::SolidColor::new(&Vec3::new(7.0, 7.0, 7.0)))));
+        // Light, only shining down (the rect's normal points up)
+        let mut diff_light =
+            material::DiffuseLight::new(
+                Arc::new(texture::SolidColor::new(&Vec3::new(7.0, 7.0, 7.0))));
+        diff_light.sides = material::EmitSides::Back;
+        let diff_light_material = Arc::new(diff_light);
         world.list.push(Arc::new(
         hittable::XZRect::new(123.0, 423.0, 147.0, 412.0, 554.0, diff_light_material.clone())));
 
@@ -601,7 +641,7 @@ pub fn second_weekend_final_scene(image_width: u32, image_height: u32, samples_p
         world.list.push(Arc::new(MovingSphere { center0: center1, center1: center2, time0: 0.0, time1: 1.0, radius: 50.0, material: moving_sphere_material }));
 
         // Dielectric
-        let dielectric_material = Arc::new(material::Dielectric { index_of_refraction: 1.5 });
+        let dielectric_material = Arc::new(material::Dielectric::new(1.5));
         world.list.push(Arc::new(Sphere { center: Vec3::new(260.0, 150.0, 145.0), radius: 50.0, material: dielectric_material.clone() }));
 
         // Metal sphere
@@ -647,5 +687,647 @@ pub fn second_weekend_final_scene(image_width: u32, image_height: u32, samples_p
 
     // Return
     let params = example_params(image_width, image_height, samples_per_pixel, max_depth);
-    (params, example_camera(params.aspect_ratio), example_scene(earth_image))
-}
\ No newline at end of file
+    (params, example_camera(params.aspect_ratio), Scene::new(example_scene(earth_image), Arc::new(ConstantBackground::new(&Vec3::default()))))
+}
+// --------------------------------------------------------------------------------------------------------------------
+
+pub fn lights_example(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::ProjectiveCamera, Scene)  {
+    fn example_params(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> RaytracerParams {
+        RaytracerParams {
+            aspect_ratio: (image_width as Float) / (image_height as Float),
+            image_width,
+            image_height,
+            samples_per_pixel,
+            max_depth,
+            filter: PixelFilter::default(),
+            sampler: SamplerType::default(),
+            seed: 0,
+            next_event_estimation: true,
+        }
+    }
+
+    pub fn example_camera(aspect_ratio: Float) -> camera::ProjectiveCamera {
+        let camera;
+        {
+            let look_from = Vec3::new(13.0, 4.0, 5.0);
+            let look_at = Vec3::new(0.0, 1.0, 0.0);
+            let up = Vec3::new(0.0, 1.0, 0.0);
+            let focus_dist = 10.0;
+            let aperture = 0.0;
+
+            camera = camera::ProjectiveCamera::new(
+                &look_from,
+                &look_at,
+                &up,
+                35.0,
+                aspect_ratio,
+                aperture,
+                focus_dist,
+                0.0,
+                1.0
+            );
+        }
+
+        camera
+    }
+
+    fn example_scene() -> HittableList {
+        let mut world = HittableList::default();
+
+        let ground_material = Arc::new(material::Lambertian::new(Arc::new(texture::SolidColor::new(&Vec3::new(0.6, 0.6, 0.6)))));
+        world.list.push(Arc::new(Sphere { center: Vec3::new(0.0, -1000.0, 0.0), radius: 1000.0, material: ground_material }));
+
+        let diffuse_material = Arc::new(material::Lambertian::new(Arc::new(texture::SolidColor::new(&Vec3::new(0.8, 0.3, 0.2)))));
+        world.list.push(Arc::new(Sphere { center: Vec3::new(0.0, 1.0, -2.5), radius: 1.0, material: diffuse_material }));
+
+        let metal_material = Arc::new(material::Metal { albedo: Vec3::new(0.8, 0.8, 0.8), fuzz: 0.2 });
+        world.list.push(Arc::new(Sphere { center: Vec3::new(0.0, 1.0, 0.0), radius: 1.0, material: metal_material }));
+
+        let glass_material = Arc::new(material::Dielectric::new(1.5));
+        world.list.push(Arc::new(Sphere { center: Vec3::new(0.0, 1.0, 2.5), radius: 1.0, material: glass_material }));
+
+        // A glowing ball, 2700K like an incandescent bulb, giving off 60 watts
+        let bulb_radius = 0.4;
+        let bulb_area = 4.0 * (std::f32::consts::PI as Float) * bulb_radius * bulb_radius;
+        let bulb_material = Arc::new(material::DiffuseLight::new_with_temperature(2700.0, material::LightPower::Watts(60.0), bulb_area,
+            material::EmitSides::Front));
+        world.list.push(Arc::new(Sphere { center: Vec3::new(2.5, bulb_radius, -1.5), radius: bulb_radius, material: bulb_material }));
+
+        world
+    }
+
+    // Warm point light on the left, a cool spot light on the glass sphere and dim moonlight from behind
+    fn example_lights() -> Vec<Arc<dyn Light>> {
+        vec![
+            Arc::new(PointLight::new(&Vec3::new(3.0, 4.0, -5.0), &Vec3::new(40.0, 30.0, 20.0))),
+            Arc::new(SpotLight::new(&Vec3::new(4.0, 6.0, 4.0), &Vec3::new(0.0, 0.0, 2.5), &Vec3::new(40.0, 50.0, 70.0), 20.0, 12.0)),
+            Arc::new(DirectionalLight::new(&Vec3::new(-1.0, -1.0, 0.5), &Vec3::new(0.15, 0.17, 0.25), 0.53))
+        ]
+    }
+
+    // Return
+    let params = example_params(image_width, image_height, samples_per_pixel, max_depth);
+    let mut scene = Scene::new(example_scene(), Arc::new(ConstantBackground::new(&Vec3::new(0.01, 0.01, 0.02))));
+    scene.lights = example_lights();
+    (params, example_camera(params.aspect_ratio), scene)
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+
+// A wall-washing downlight with a wider beam along its length (+x) than across it
+const WALL_WASHER_IES: &str = "IESNA:LM-63-2002
+[TEST] YARR example profile
+[MANUFAC] YARR
+[LUMINAIRE] Wall washer downlight
+TILT=NONE
+1 1000 1.0 10 2 1 2 0.1 0.1 0.05
+1.0 1.0 12
+0 10 20 30 40 50 60 70 80 90
+0 90
+1800 1900 2100 2200 1600 700 200 60 20 0
+1800 1850 1900 1700 1000 400 120 40 10 0
+";
+
+pub fn ies_lights_example(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::ProjectiveCamera, Scene)  {
+    fn example_params(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> RaytracerParams {
+        RaytracerParams {
+            aspect_ratio: (image_width as Float) / (image_height as Float),
+            image_width,
+            image_height,
+            samples_per_pixel,
+            max_depth,
+            filter: PixelFilter::default(),
+            sampler: SamplerType::default(),
+            seed: 0,
+            next_event_estimation: true,
+        }
+    }
+
+    pub fn example_camera(aspect_ratio: Float) -> camera::ProjectiveCamera {
+        let camera;
+        {
+            let look_from = Vec3::new(0.0, 1.8, 6.0);
+            let look_at = Vec3::new(0.0, 1.6, -3.0);
+            let up = Vec3::new(0.0, 1.0, 0.0);
+            let focus_dist = 10.0;
+            let aperture = 0.0;
+
+            camera = camera::ProjectiveCamera::new(
+                &look_from,
+                &look_at,
+                &up,
+                55.0,
+                aspect_ratio,
+                aperture,
+                focus_dist,
+                0.0,
+                1.0
+            );
+        }
+
+        camera
+    }
+
+    fn example_scene() -> HittableList {
+        let mut world = HittableList::default();
+
+        let white_material = Arc::new(material::Lambertian::new(Arc::new(texture::SolidColor::new(&Vec3::new(0.75, 0.75, 0.75)))));
+        world.list.push(Arc::new(hittable::XYRect::new(-8.0, 8.0, 0.0, 4.0, -3.0, white_material.clone())));
+        world.list.push(Arc::new(hittable::XZRect::new(-8.0, 8.0, -3.0, 8.0, 0.0, white_material.clone())));
+
+        let sphere_material = Arc::new(material::Lambertian::new(Arc::new(texture::SolidColor::new(&Vec3::new(0.2, 0.4, 0.7)))));
+        world.list.push(Arc::new(Sphere { center: Vec3::new(0.0, 0.6, -2.0), radius: 0.6, material: sphere_material }));
+
+        world
+    }
+
+    // A row of downlights close to the wall, the middle one tilted towards the sphere
+    fn example_lights() -> Vec<Arc<dyn Light>> {
+        let profile = Arc::new(IesProfile::parse(WALL_WASHER_IES).unwrap());
+        let intensity = Vec3::new(0.012, 0.011, 0.009);
+
+        vec![
+            Arc::new(GoniometricLight::new_point(&Vec3::new(-3.5, 3.8, -2.5), profile.clone(), &intensity)),
+            Arc::new(GoniometricLight::new_spot(&Vec3::new(0.0, 3.8, -2.5), &Vec3::new(0.0, 0.0, -2.0), profile.clone(), &intensity, Option::None)),
+            Arc::new(GoniometricLight::new_point(&Vec3::new(3.5, 3.8, -2.5), profile.clone(), &intensity))
+        ]
+    }
+
+    // Return
+    let params = example_params(image_width, image_height, samples_per_pixel, max_depth);
+    let mut scene = Scene::new(example_scene(), Arc::new(ConstantBackground::new(&Vec3::default())));
+    scene.lights = example_lights();
+    (params, example_camera(params.aspect_ratio), scene)
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+
+pub fn motion_blur_example(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::ProjectiveCamera, Scene)  {
+    fn example_params(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> RaytracerParams {
+        RaytracerParams {
+            aspect_ratio: (image_width as Float) / (image_height as Float),
+            image_width,
+            image_height,
+            samples_per_pixel,
+            max_depth,
+            filter: PixelFilter::default(),
+            sampler: SamplerType::default(),
+            seed: 0,
+            next_event_estimation: true,
+        }
+    }
+
+    pub fn example_camera(aspect_ratio: Float) -> camera::ProjectiveCamera {
+        let camera;
+        {
+            let look_from = Vec3::new(0.0, 3.0, 10.0);
+            let look_at = Vec3::new(0.0, 1.0, 0.0);
+            let up = Vec3::new(0.0, 1.0, 0.0);
+            let focus_dist = 10.0;
+            let aperture = 0.0;
+
+            camera = camera::ProjectiveCamera::new(
+                &look_from,
+                &look_at,
+                &up,
+                40.0,
+                aspect_ratio,
+                aperture,
+                focus_dist,
+                0.0,
+                1.0
+            );
+        }
+
+        camera
+    }
+
+    fn example_scene() -> HittableList {
+        let mut world = HittableList::default();
+
+        let ground_material = Arc::new(material::Lambertian::new(Arc::new(texture::Checker::new_from_colors(&Vec3::new(0.2, 0.3, 0.1), &Vec3::new(0.9, 0.9, 0.9)))));
+        world.list.push(Arc::new(Sphere { center: Vec3::new(0.0, -1000.0, 0.0), radius: 1000.0, material: ground_material }));
+
+        // A box tumbling to the right while the shutter is open
+        let box_material = Arc::new(material::Lambertian::new(Arc::new(texture::SolidColor::new(&Vec3::new(0.8, 0.3, 0.2)))));
+        let unit_box = Arc::new(hittable::Box::new(&Vec3::new(-0.6, -0.6, -0.6), &Vec3::new(0.6, 0.6, 0.6), box_material));
+        world.list.push(Arc::new(hittable::AnimatedTransform::new(unit_box, &[
+            hittable::TransformKey::new(0.0, &Vec3::new(-3.2, 0.8, 0.0), &Vec3::new(0.0, 0.0, 0.0), &Vec3::new(1.0, 1.0, 1.0)),
+            hittable::TransformKey::new(1.0, &Vec3::new(-2.2, 0.8, 0.0), &Vec3::new(0.0, 30.0, -90.0), &Vec3::new(1.0, 1.0, 1.0))
+        ], Interpolation::Linear)));
+
+        // A group of spheres spinning around its center
+        let mut group = HittableList::default();
+        let metal_material = Arc::new(material::Metal { albedo: Vec3::new(0.8, 0.8, 0.8), fuzz: 0.1 });
+        let blue_material = Arc::new(material::Lambertian::new(Arc::new(texture::SolidColor::new(&Vec3::new(0.2, 0.4, 0.8)))));
+        group.list.push(Arc::new(Sphere { center: Vec3::new(0.0, 0.0, 0.0), radius: 0.5, material: metal_material }));
+        for i in 0..4 {
+            let angle = (i as Float) * (std::f32::consts::FRAC_PI_2 as Float);
+            group.list.push(Arc::new(Sphere { center: Vec3::new(angle.cos() * 1.1, 0.0, angle.sin() * 1.1), radius: 0.3, material: blue_material.clone() }));
+        }
+        world.list.push(Arc::new(hittable::AnimatedTransform::new(Arc::new(group), &[
+            hittable::TransformKey::new(0.0, &Vec3::new(1.0, 1.0, 0.0), &Vec3::new(0.0, 0.0, 0.0), &Vec3::new(1.0, 1.0, 1.0)),
+            hittable::TransformKey::new(1.0, &Vec3::new(1.0, 1.0, 0.0), &Vec3::new(0.0, 40.0, 0.0), &Vec3::new(1.0, 1.0, 1.0))
+        ], Interpolation::Linear)));
+
+        // A sphere squashing and growing on the right
+        let yellow_material = Arc::new(material::Lambertian::new(Arc::new(texture::SolidColor::new(&Vec3::new(0.9, 0.8, 0.2)))));
+        let ball = Arc::new(Sphere { center: Vec3::new(0.0, 0.0, 0.0), radius: 1.0, material: yellow_material });
+        world.list.push(Arc::new(hittable::AnimatedTransform::new(ball, &[
+            hittable::TransformKey::new(0.0, &Vec3::new(3.2, 0.5, 0.0), &Vec3::new(0.0, 0.0, 0.0), &Vec3::new(0.8, 0.5, 0.8)),
+            hittable::TransformKey::new(0.5, &Vec3::new(3.2, 0.8, 0.0), &Vec3::new(0.0, 0.0, 0.0), &Vec3::new(0.7, 0.8, 0.7)),
+            hittable::TransformKey::new(1.0, &Vec3::new(3.2, 0.9, 0.0), &Vec3::new(0.0, 0.0, 0.0), &Vec3::new(0.9, 0.9, 0.9))
+        ], Interpolation::Spline)));
+
+        world
+    }
+
+    // Return
+    let params = example_params(image_width, image_height, samples_per_pixel, max_depth);
+    (params, example_camera(params.aspect_ratio), Scene::new(example_scene(), Arc::new(GradientBackground::default())))
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+
+pub fn materials_example(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::ProjectiveCamera, Scene)  {
+    fn example_params(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> RaytracerParams {
+        RaytracerParams {
+            aspect_ratio: (image_width as Float) / (image_height as Float),
+            image_width,
+            image_height,
+            samples_per_pixel,
+            max_depth,
+            filter: PixelFilter::default(),
+            sampler: SamplerType::default(),
+            seed: 0,
+            next_event_estimation: true,
+        }
+    }
+
+    pub fn example_camera(aspect_ratio: Float) -> camera::ProjectiveCamera {
+        let camera;
+        {
+            let look_from = Vec3::new(0.0, 6.0, 11.0);
+            let look_at = Vec3::new(0.0, 0.9, 0.0);
+            let up = Vec3::new(0.0, 1.0, 0.0);
+            let focus_dist = 12.0;
+            let aperture = 0.0;
+
+            camera = camera::ProjectiveCamera::new(
+                &look_from,
+                &look_at,
+                &up,
+                35.0,
+                aspect_ratio,
+                aperture,
+                focus_dist,
+                0.0,
+                1.0
+            );
+        }
+
+        camera
+    }
+
+    fn scalar(value: Float) -> Arc<dyn texture::Texture> {
+        Arc::new(texture::SolidColor::new_scalar(value))
+    }
+
+    fn example_scene() -> HittableList {
+        let mut world = HittableList::default();
+
+        let ground_material = Arc::new(material::Lambertian::new(Arc::new(texture::Checker::new_from_colors(&Vec3::new(0.3, 0.3, 0.3), &Vec3::new(0.8, 0.8, 0.8)))));
+        world.list.push(Arc::new(Sphere { center: Vec3::new(0.0, -1000.0, 0.0), radius: 1000.0, material: ground_material }));
+
+        // Front row: plastic, rough gold, car paint with a clearcoat
+        let plastic = material::Principled::new_from_color(&Vec3::new(0.1, 0.3, 0.8));
+
+        let mut gold = material::Principled::new_from_color(&Vec3::new(1.0, 0.78, 0.34));
+        gold.metallic = scalar(1.0);
+        gold.roughness = scalar(0.35);
+
+        let mut car_paint = material::Principled::new_from_color(&Vec3::new(0.6, 0.02, 0.02));
+        car_paint.roughness = scalar(0.6);
+        car_paint.clearcoat = scalar(1.0);
+
+        let mut velvet = material::Principled::new_from_color(&Vec3::new(0.35, 0.05, 0.4));
+        velvet.roughness = scalar(1.0);
+        velvet.sheen = scalar(1.0);
+
+        // Back row: velvet with sheen, glass tinted green, and metal or rubber by a checker texture
+        let mut glass = material::Principled::new_from_color(&Vec3::new(0.8, 1.0, 0.85));
+        glass.roughness = scalar(0.0);
+        glass.transmission = scalar(1.0);
+
+        let mut checkered = material::Principled::new_from_color(&Vec3::new(0.9, 0.9, 0.9));
+        checkered.metallic = Arc::new(texture::Checker::new_from_colors(&Vec3::new(1.0, 1.0, 1.0), &Vec3::new(0.0, 0.0, 0.0)));
+        checkered.roughness = Arc::new(texture::Checker::new_from_colors(&Vec3::new(0.15, 0.15, 0.15), &Vec3::new(0.9, 0.9, 0.9)));
+
+        let materials: Vec<Arc<dyn material::Material>> = vec![Arc::new(plastic), Arc::new(gold), Arc::new(car_paint), Arc::new(velvet), Arc::new(glass), Arc::new(checkered)];
+        for (i, material) in materials.into_iter().enumerate() {
+            let center = Vec3::new(-2.4 + 2.4 * ((i % 3) as Float), 1.0, if i < 3 { 1.4 } else { -1.4 });
+            world.list.push(Arc::new(Sphere { center, radius: 1.0, material }));
+        }
+
+        world
+    }
+
+    // A soft key light from the upper left on top of the sky
+    fn example_lights() -> Vec<Arc<dyn Light>> {
+        vec![
+            Arc::new(PointLight::new(&Vec3::new(-6.0, 8.0, 6.0), &Vec3::new(60.0, 58.0, 55.0)))
+        ]
+    }
+
+    // Return
+    let params = example_params(image_width, image_height, samples_per_pixel, max_depth);
+    let mut scene = Scene::new(example_scene(), Arc::new(GradientBackground::default()));
+    scene.lights = example_lights();
+    (params, example_camera(params.aspect_ratio), scene)
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+
+pub fn metals_example(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::ProjectiveCamera, Scene)  {
+    fn example_params(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> RaytracerParams {
+        RaytracerParams {
+            aspect_ratio: (image_width as Float) / (image_height as Float),
+            image_width,
+            image_height,
+            samples_per_pixel,
+            max_depth,
+            filter: PixelFilter::default(),
+            sampler: SamplerType::default(),
+            seed: 0,
+            next_event_estimation: true,
+        }
+    }
+
+    pub fn example_camera(aspect_ratio: Float) -> camera::ProjectiveCamera {
+        let camera;
+        {
+            let look_from = Vec3::new(0.0, 6.0, 11.0);
+            let look_at = Vec3::new(0.0, 0.9, 0.0);
+            let up = Vec3::new(0.0, 1.0, 0.0);
+            let focus_dist = 12.0;
+            let aperture = 0.0;
+
+            camera = camera::ProjectiveCamera::new(
+                &look_from,
+                &look_at,
+                &up,
+                35.0,
+                aspect_ratio,
+                aperture,
+                focus_dist,
+                0.0,
+                1.0
+            );
+        }
+
+        camera
+    }
+
+    fn example_scene() -> HittableList {
+        let mut world = HittableList::default();
+
+        let ground_material = Arc::new(material::Lambertian::new(Arc::new(texture::Checker::new_from_colors(&Vec3::new(0.3, 0.3, 0.3), &Vec3::new(0.8, 0.8, 0.8)))));
+        world.list.push(Arc::new(Sphere { center: Vec3::new(0.0, -1000.0, 0.0), radius: 1000.0, material: ground_material }));
+
+        // Front row: gold, copper and silver getting rougher
+        let mut materials: Vec<Arc<dyn material::Material>> = vec![
+            Arc::new(material::Conductor::new_from_preset(material::MetalPreset::Gold, 0.05)),
+            Arc::new(material::Conductor::new_from_preset(material::MetalPreset::Copper, 0.3)),
+            Arc::new(material::Conductor::new_from_preset(material::MetalPreset::Silver, 0.6))
+        ];
+
+        // Back row: aluminium brushed around the y axis, chrome brushed around the x axis, and iron polished or rough
+        // by a checker texture
+        let mut brushed = material::Conductor::new_from_preset(material::MetalPreset::Aluminium, 0.4);
+        brushed.anisotropy = Arc::new(texture::SolidColor::new_scalar(0.9));
+        materials.push(Arc::new(brushed));
+
+        let mut brushed_sideways = material::Conductor::new_from_preset(material::MetalPreset::Chrome, 0.4);
+        brushed_sideways.anisotropy = Arc::new(texture::SolidColor::new_scalar(0.9));
+        brushed_sideways.tangent = Vec3::new(1.0, 0.0, 0.0);
+        materials.push(Arc::new(brushed_sideways));
+
+        let (eta, k) = material::MetalPreset::Iron.ior();
+        let roughness = Arc::new(texture::Checker::new_from_colors(&Vec3::new(0.05, 0.05, 0.05), &Vec3::new(0.5, 0.5, 0.5)));
+        materials.push(Arc::new(material::Conductor::new_from_ior(&eta, &k, roughness)));
+
+        for (i, material) in materials.into_iter().enumerate() {
+            let center = Vec3::new(-2.4 + 2.4 * ((i % 3) as Float), 1.0, if i < 3 { 1.4 } else { -1.4 });
+            world.list.push(Arc::new(Sphere { center, radius: 1.0, material }));
+        }
+
+        world
+    }
+
+    fn example_lights() -> Vec<Arc<dyn Light>> {
+        vec![
+            Arc::new(PointLight::new(&Vec3::new(-6.0, 8.0, 6.0), &Vec3::new(60.0, 58.0, 55.0)))
+        ]
+    }
+
+    // Return
+    let params = example_params(image_width, image_height, samples_per_pixel, max_depth);
+    let mut scene = Scene::new(example_scene(), Arc::new(GradientBackground::default()));
+    scene.lights = example_lights();
+    (params, example_camera(params.aspect_ratio), scene)
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+
+pub fn glass_example(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::ProjectiveCamera, Scene)  {
+    fn example_params(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> RaytracerParams {
+        RaytracerParams {
+            aspect_ratio: (image_width as Float) / (image_height as Float),
+            image_width,
+            image_height,
+            samples_per_pixel,
+            max_depth,
+            filter: PixelFilter::default(),
+            sampler: SamplerType::default(),
+            seed: 0,
+            next_event_estimation: true,
+        }
+    }
+
+    pub fn example_camera(aspect_ratio: Float) -> camera::ProjectiveCamera {
+        let camera;
+        {
+            let look_from = Vec3::new(0.0, 5.0, 12.0);
+            let look_at = Vec3::new(0.0, 0.6, -0.5);
+            let up = Vec3::new(0.0, 1.0, 0.0);
+            let focus_dist = 12.0;
+            let aperture = 0.0;
+
+            camera = camera::ProjectiveCamera::new(
+                &look_from,
+                &look_at,
+                &up,
+                45.0,
+                aspect_ratio,
+                aperture,
+                focus_dist,
+                0.0,
+                1.0
+            );
+        }
+
+        camera
+    }
+
+    fn example_scene() -> HittableList {
+        let mut world = HittableList::default();
+
+        let ground_material = Arc::new(material::Lambertian::new(Arc::new(texture::Checker::new_from_colors(&Vec3::new(0.3, 0.3, 0.3), &Vec3::new(0.8, 0.8, 0.8)))));
+        world.list.push(Arc::new(Sphere { center: Vec3::new(0.0, -1000.0, 0.0), radius: 1000.0, material: ground_material }));
+
+        // Colored balls behind the glass, to see how much each one blurs them
+        let colors = [Vec3::new(0.8, 0.1, 0.1), Vec3::new(0.1, 0.7, 0.2), Vec3::new(0.1, 0.2, 0.8), Vec3::new(0.9, 0.7, 0.1)];
+        for (i, color) in colors.iter().enumerate() {
+            let material = Arc::new(material::Lambertian::new(Arc::new(texture::SolidColor::new(color))));
+            world.list.push(Arc::new(Sphere { center: Vec3::new(-3.3 + 2.2 * (i as Float), 0.5, -4.0), radius: 0.5, material }));
+        }
+
+        // Back row: smooth glass, frosted glass getting rougher, and glass frosted in stripes by a checker texture
+        let roughness = Arc::new(texture::Checker::new_from_colors(&Vec3::new(0.0, 0.0, 0.0), &Vec3::new(0.4, 0.4, 0.4)));
+        let mut materials: Vec<Arc<dyn material::Material>> = vec![
+            Arc::new(material::Dielectric::new(1.5)),
+            Arc::new(material::RoughDielectric::new(1.5, Arc::new(texture::SolidColor::new_scalar(0.15)))),
+            Arc::new(material::RoughDielectric::new(1.5, Arc::new(texture::SolidColor::new_scalar(0.4)))),
+            Arc::new(material::RoughDielectric::new(1.5, roughness))
+        ];
+
+        // Front row: green and amber glass, deeper colored where it's thicker, and flint glass and diamond splitting
+        // light into colors
+        let mut green = material::Dielectric::new(1.5);
+        green.set_color_at_distance(&Vec3::new(0.3, 0.8, 0.4), 1.0);
+        materials.push(Arc::new(green));
+
+        let mut amber = material::Dielectric::new(1.5);
+        amber.set_color_at_distance(&Vec3::new(0.9, 0.5, 0.1), 1.0);
+        materials.push(Arc::new(amber));
+
+        let mut flint = material::Dielectric::new(1.5);
+        flint.dispersion = Some(material::Dispersion::flint_glass());
+        materials.push(Arc::new(flint));
+
+        let mut diamond = material::Dielectric::new(2.4);
+        diamond.dispersion = Some(material::Dispersion::diamond());
+        materials.push(Arc::new(diamond));
+
+        for (i, material) in materials.into_iter().enumerate() {
+            let center = Vec3::new(-3.3 + 2.2 * ((i % 4) as Float), 1.0, if i < 4 { -1.2 } else { 1.6 });
+            world.list.push(Arc::new(Sphere { center, radius: 1.0, material }));
+        }
+
+        world
+    }
+
+    fn example_lights() -> Vec<Arc<dyn Light>> {
+        vec![
+            Arc::new(PointLight::new(&Vec3::new(-6.0, 8.0, 6.0), &Vec3::new(60.0, 58.0, 55.0)))
+        ]
+    }
+
+    // Return
+    let params = example_params(image_width, image_height, samples_per_pixel, max_depth);
+    let mut scene = Scene::new(example_scene(), Arc::new(GradientBackground::default()));
+    scene.lights = example_lights();
+    (params, example_camera(params.aspect_ratio), scene)
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+
+pub fn coated_example(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> (RaytracerParams, camera::ProjectiveCamera, Scene)  {
+    fn example_params(image_width: u32, image_height: u32, samples_per_pixel: u32, max_depth: u32) -> RaytracerParams {
+        RaytracerParams {
+            aspect_ratio: (image_width as Float) / (image_height as Float),
+            image_width,
+            image_height,
+            samples_per_pixel,
+            max_depth,
+            filter: PixelFilter::default(),
+            sampler: SamplerType::default(),
+            seed: 0,
+            next_event_estimation: true,
+        }
+    }
+
+    pub fn example_camera(aspect_ratio: Float) -> camera::ProjectiveCamera {
+        let camera;
+        {
+            let look_from = Vec3::new(0.0, 6.0, 11.0);
+            let look_at = Vec3::new(0.0, 0.9, 0.0);
+            let up = Vec3::new(0.0, 1.0, 0.0);
+            let focus_dist = 12.0;
+            let aperture = 0.0;
+
+            camera = camera::ProjectiveCamera::new(
+                &look_from,
+                &look_at,
+                &up,
+                35.0,
+                aspect_ratio,
+                aperture,
+                focus_dist,
+                0.0,
+                1.0
+            );
+        }
+
+        camera
+    }
+
+    fn lambertian(color: &Vec3<Float>) -> Arc<dyn material::Material> {
+        Arc::new(material::Lambertian::new(Arc::new(texture::SolidColor::new(color))))
+    }
+
+    fn example_scene() -> HittableList {
+        let mut world = HittableList::default();
+
+        let ground_material = Arc::new(material::Lambertian::new(Arc::new(texture::Checker::new_from_colors(&Vec3::new(0.3, 0.3, 0.3), &Vec3::new(0.8, 0.8, 0.8)))));
+        world.list.push(Arc::new(Sphere { center: Vec3::new(0.0, -1000.0, 0.0), radius: 1000.0, material: ground_material }));
+
+        // Front row: the same red with no coat, car paint with a smooth coat, and glossy plastic with a slightly rough one
+        let red = Vec3::new(0.7, 0.05, 0.05);
+        let car_paint = material::Coated::new(lambertian(&red));
+
+        let mut plastic = material::Coated::new(lambertian(&red));
+        plastic.roughness = Arc::new(texture::SolidColor::new_scalar(0.25));
+
+        // Back row: varnished "wood", an amber coat over marble, lacquered gold, and a thick green enamel over white
+        let mut varnished = material::Coated::new(Arc::new(material::Lambertian::new(Arc::new(texture::Noise::new(4.0)))));
+        varnished.tint = Vec3::new(0.9, 0.6, 0.3);
+
+        let mut lacquered = material::Coated::new(Arc::new(material::Conductor::new_from_preset(material::MetalPreset::Gold, 0.3)));
+        lacquered.index_of_refraction = 1.6;
+
+        let mut enamel = material::Coated::new(lambertian(&Vec3::new(0.9, 0.9, 0.9)));
+        enamel.tint = Vec3::new(0.5, 0.85, 0.6);
+        enamel.thickness = 2.0;
+
+        let materials: Vec<Arc<dyn material::Material>> = vec![lambertian(&red), Arc::new(car_paint), Arc::new(plastic), Arc::new(varnished), Arc::new(lacquered), Arc::new(enamel)];
+        for (i, material) in materials.into_iter().enumerate() {
+            let center = Vec3::new(-2.4 + 2.4 * ((i % 3) as Float), 1.0, if i < 3 { 1.4 } else { -1.4 });
+            world.list.push(Arc::new(Sphere { center, radius: 1.0, material }));
+        }
+
+        world
+    }
+
+    fn example_lights() -> Vec<Arc<dyn Light>> {
+        vec![
+            Arc::new(PointLight::new(&Vec3::new(-6.0, 8.0, 6.0), &Vec3::new(60.0, 58.0, 55.0)))
+        ]
+    }
+
+    // Return
+    let params = example_params(image_width, image_height, samples_per_pixel, max_depth);
+    let mut scene = Scene::new(example_scene(), Arc::new(GradientBackground::default()));
+    scene.lights = example_lights();
+    (params, example_camera(params.aspect_ratio), scene)
+}
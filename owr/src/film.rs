@@ -0,0 +1,107 @@
+use crate::types::*;
+use crate::vec3::Vec3;
+use crate::filter::PixelFilter;
+
+// --------------------------------------------------------------------------------------------------------------------
+// Film: a rectangle of pixels that accumulates filter weighted samples
+
+pub struct Film {
+    pub x0: u32,
+    pub y0: u32,
+    pub width: u32,
+    pub height: u32,
+    weighted_sum: Vec<Vec3<Float>>,
+    weight_sum: Vec<Float>
+}
+
+impl Film {
+    pub fn new(x0: u32, y0: u32, width: u32, height: u32) -> Self {
+        let num_pixels = (width * height) as usize;
+
+        Self {
+            x0,
+            y0,
+            width,
+            height,
+            weighted_sum: vec![Vec3::default(); num_pixels],
+            weight_sum: vec![0.0; num_pixels]
+        }
+    }
+
+    // Splats a sample taken at image position (film_x, film_y) into every pixel of this film
+    // whose filter footprint covers it. Pixel centers sit at half-integer positions, and a pixel is reached when its
+    // center is in (-radius, radius] of the sample, like PixelFilter::evaluate.
+    pub fn add_sample(&mut self, filter: &PixelFilter, film_x: Float, film_y: Float, color: &Vec3<Float>) {
+        let x_min = i64::max((film_x - 0.5 - filter.radius).floor() as i64 + 1, self.x0 as i64);
+        let x_max = i64::min((film_x - 0.5 + filter.radius).floor() as i64, (self.x0 + self.width) as i64 - 1);
+        let y_min = i64::max((film_y - 0.5 - filter.radius).floor() as i64 + 1, self.y0 as i64);
+        let y_max = i64::min((film_y - 0.5 + filter.radius).floor() as i64, (self.y0 + self.height) as i64 - 1);
+
+        for y in y_min..=y_max {
+            for x in x_min..=x_max {
+                let weight = filter.evaluate((x as Float) + 0.5 - film_x, (y as Float) + 0.5 - film_y);
+                if weight != 0.0 {
+                    let index = self.index(x as u32, y as u32);
+                    self.weighted_sum[index] = self.weighted_sum[index] + (*color * weight);
+                    self.weight_sum[index] += weight;
+                }
+            }
+        }
+    }
+
+    // Adds the overlapping part of another film, used to stitch separately rendered tiles
+    pub fn add_film(&mut self, other: &Film) {
+        let x_min = u32::max(self.x0, other.x0);
+        let x_max = u32::min(self.x0 + self.width, other.x0 + other.width);
+        let y_min = u32::max(self.y0, other.y0);
+        let y_max = u32::min(self.y0 + self.height, other.y0 + other.height);
+
+        for y in y_min..y_max {
+            for x in x_min..x_max {
+                let index = self.index(x, y);
+                let other_index = other.index(x, y);
+                self.weighted_sum[index] = self.weighted_sum[index] + other.weighted_sum[other_index];
+                self.weight_sum[index] += other.weight_sum[other_index];
+            }
+        }
+    }
+
+    // Final pixel value, in absolute image coordinates
+    pub fn pixel(&self, x: u32, y: u32) -> Vec3<Float> {
+        let index = self.index(x, y);
+        if self.weight_sum[index] > 0.0 {
+            self.weighted_sum[index] * (1.0 / self.weight_sum[index])
+        } else {
+            Vec3::default()
+        }
+    }
+
+    // All final pixel values, row by row
+    pub fn pixels(&self) -> Vec<Vec3<Float>> {
+        (self.y0..(self.y0 + self.height))
+            .flat_map(|y| (self.x0..(self.x0 + self.width)).map(move |x| (x, y)))
+            .map(|(x, y)| self.pixel(x, y))
+            .collect()
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        ((y - self.y0) * self.width + (x - self.x0)) as usize
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn box_filter_sample_on_a_pixel_border_lands_in_one_pixel() {
+        let mut film = Film::new(0, 0, 3, 3);
+        film.add_sample(&PixelFilter::default(), 1.0, 2.0, &Vec3::new(1.0, 1.0, 1.0));
+
+        // Pixel (1, 2) covers [1, 2) x [2, 3)
+        let touched: Vec<usize> = (0..9).filter(|&i| film.weight_sum[i] != 0.0).collect();
+        assert_eq!(touched, vec![film.index(1, 2)]);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::types::*;
+
+// --------------------------------------------------------------------------------------------------------------------
+// Reconstruction filter types
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum FilterType {
+    Box,
+    Tent,
+    Gaussian,
+    Mitchell,
+    BlackmanHarris
+}
+
+impl FilterType {
+    pub fn from_name(name: &str) -> Option<FilterType> {
+        match name {
+            "box" => { Some(FilterType::Box) }
+            "tent" | "triangle" => { Some(FilterType::Tent) }
+            "gaussian" => { Some(FilterType::Gaussian) }
+            "mitchell" => { Some(FilterType::Mitchell) }
+            "blackman-harris" => { Some(FilterType::BlackmanHarris) }
+            _ => { None }
+        }
+    }
+
+    // Radius (in pixels) each filter is usually run with
+    pub fn default_radius(&self) -> Float {
+        match self {
+            FilterType::Box => { 0.5 }
+            FilterType::Tent => { 1.0 }
+            FilterType::Gaussian => { 1.5 }
+            FilterType::Mitchell => { 2.0 }
+            FilterType::BlackmanHarris => { 1.5 }
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Pixel filter
+
+#[derive(Copy, Clone)]
+pub struct PixelFilter {
+    pub filter_type: FilterType,
+    pub radius: Float
+}
+
+impl Default for PixelFilter {
+    // A half pixel box, i.e. every sample only lands in the pixel it was taken in
+    fn default() -> Self {
+        PixelFilter::new(FilterType::Box, FilterType::Box.default_radius())
+    }
+}
+
+impl PixelFilter {
+    pub fn new(filter_type: FilterType, radius: Float) -> Self {
+        Self {
+            filter_type,
+            radius
+        }
+    }
+
+    // How many neighbouring pixels a sample taken inside a pixel can reach
+    pub fn pixel_margin(&self) -> u32 {
+        Float::max(0.0, (self.radius - 0.5).ceil()) as u32
+    }
+
+    // Weight of a sample at offset (dx, dy) from a pixel center. Filters are separable.
+    pub fn evaluate(&self, dx: Float, dy: Float) -> Float {
+        self.evaluate_1d(dx) * self.evaluate_1d(dy)
+    }
+
+    // The support is half-open, (-radius, radius], so with the box filter a sample on the border between two pixels
+    // only counts for one of them
+    fn evaluate_1d(&self, x: Float) -> Float {
+        if x <= -self.radius || x > self.radius {
+            return 0.0;
+        }
+        let x = x.abs();
+
+        match self.filter_type {
+            FilterType::Box => { 1.0 }
+            FilterType::Tent => { self.radius - x }
+            FilterType::Gaussian => {
+                // Shifted down so the filter reaches zero at its radius
+                let sigma = self.radius / 3.0;
+                let gaussian = |x: Float| Float::exp(-(x * x) / (2.0 * sigma * sigma));
+                Float::max(0.0, gaussian(x) - gaussian(self.radius))
+            }
+            FilterType::Mitchell => {
+                PixelFilter::mitchell_1d(2.0 * x / self.radius, 1.0 / 3.0, 1.0 / 3.0)
+            }
+            FilterType::BlackmanHarris => {
+                let pi = std::f32::consts::PI as Float;
+                let n = 0.5 * (x / self.radius + 1.0);
+                0.35875 - 0.48829 * Float::cos(2.0 * pi * n) + 0.14128 * Float::cos(4.0 * pi * n) - 0.01168 * Float::cos(6.0 * pi * n)
+            }
+        }
+    }
+
+    // Mitchell-Netravali cubic over [0, 2]
+    fn mitchell_1d(x: Float, b: Float, c: Float) -> Float {
+        if x < 1.0 {
+            ((12.0 - 9.0*b - 6.0*c) * x*x*x + (-18.0 + 12.0*b + 6.0*c) * x*x + (6.0 - 2.0*b)) * (1.0 / 6.0)
+        } else if x < 2.0 {
+            ((-b - 6.0*c) * x*x*x + (6.0*b + 30.0*c) * x*x + (-12.0*b - 48.0*c) * x + (8.0*b + 24.0*c)) * (1.0 / 6.0)
+        } else {
+            0.0
+        }
+    }
+}
@@ -1,10 +1,11 @@
 use crate::ray::Ray;
-use crate::utils::random_float;
+use crate::utils;
 use crate::vec3::Vec3;
 use crate::material::{Material};
 use crate::aabb::Aabb;
 use crate::texture;
 use crate::material;
+use crate::animation::{self, Interpolation};
 use std::sync::Arc;
 use crate::{types::*, log_print};
 
@@ -18,7 +19,8 @@ pub struct HitRecord {
     pub u: Float,
     pub v: Float,
     pub front_facing: bool,
-    pub material: Arc<dyn Material>
+    pub material: Arc<dyn Material>,
+    pub object_id: u32
 }
 
 impl HitRecord {
@@ -32,7 +34,8 @@ impl HitRecord {
             u,
             v,
             front_facing: normal_and_direction.1, 
-            material
+            material,
+            object_id: 0
         }
     }
 
@@ -114,6 +117,18 @@ impl Hittable for HittableList {
     }
 }
 
+impl HittableList {
+    // Wraps every top-level object with a unique id (starting at 1), so hits can be told apart in id passes
+    pub fn with_object_ids(&self) -> HittableList {
+        let mut tagged = HittableList::default();
+        for (index, element) in self.list.iter().enumerate() {
+            tagged.list.push(Arc::new(ObjectTag::new(element.clone(), (index + 1) as u32)));
+        }
+
+        tagged
+    }
+}
+
 // Pull in these traits so we can enable multi-threading
 unsafe impl Sync for HittableList {}
 unsafe impl Send for HittableList {}
@@ -141,6 +156,10 @@ impl XYRect {
             material
         }
     }
+
+    pub fn area(&self) -> Float {
+        (self.x1 - self.x0) * (self.y1 - self.y0)
+    }
 }
 
 impl Hittable for XYRect {
@@ -196,6 +215,10 @@ impl XZRect {
             material
         }
     }
+
+    pub fn area(&self) -> Float {
+        (self.x1 - self.x0) * (self.z1 - self.z0)
+    }
 }
 
 impl Hittable for XZRect {
@@ -219,8 +242,8 @@ impl Hittable for XZRect {
 
     fn bounding_box(&self, _time0: Float, _time1: Float) -> Option<Aabb> {
         Some(Aabb {
-            min: Vec3::new(self.x0, self.z0, self.k - 0.0001),
-            max: Vec3::new(self.x1, self.z1, self.k + 0.0001)
+            min: Vec3::new(self.x0, self.k - 0.0001, self.z0),
+            max: Vec3::new(self.x1, self.k + 0.0001, self.z1)
         })
     }
 }
@@ -251,6 +274,10 @@ impl YZRect {
             material
         }
     }
+
+    pub fn area(&self) -> Float {
+        (self.y1 - self.y0) * (self.z1 - self.z0)
+    }
 }
 
 impl Hittable for YZRect {
@@ -274,8 +301,8 @@ impl Hittable for YZRect {
 
     fn bounding_box(&self, _time0: Float, _time1: Float) -> Option<Aabb> {
         Some(Aabb {
-            min: Vec3::new(self.y0, self.z0, self.k - 0.0001),
-            max: Vec3::new(self.y1, self.z1, self.k + 0.0001)
+            min: Vec3::new(self.k - 0.0001, self.y0, self.z0),
+            max: Vec3::new(self.k + 0.0001, self.y1, self.z1)
         })
     }
 }
@@ -357,7 +384,8 @@ impl Hittable for Translate {
         let moved_r = Ray {
             orig: r.orig - self.displacement,
             dir: r.dir,
-            time: r.time
+            time: r.time,
+            wavelength: r.wavelength
         };
 
         let hit_option = self.hittable.hit(&moved_r, t_min, t_max);
@@ -456,15 +484,11 @@ impl Hittable for RotateY {
         let rotated_r = Ray {
             orig: origin,
             dir: direction, 
-            time: r.time
+            time: r.time,
+            wavelength: r.wavelength
         };
 
-        let hit_option = self.hittable.hit(&rotated_r, t_min, t_max);
-        if hit_option.is_none() {
-            return Option::None;
-        }
-
-        let mut hit = hit_option.unwrap();
+        let mut hit = self.hittable.hit(&rotated_r, t_min, t_max)?;
 
         let mut p = hit.point.clone();
         let mut normal = hit.normal.clone();
@@ -491,6 +515,189 @@ impl Hittable for RotateY {
 unsafe impl Sync for RotateY {}
 unsafe impl Send for RotateY {}
 
+// --------------------------------------------------------------------------------------------------------------------
+// Animated transform: scales, rotates (degrees around x, then y, then z) and moves the wrapped object by keys over
+// time, so anything can move, spin or grow during the shutter and get motion blur. Each ray sees the object where it
+// is at the ray's time.
+
+#[derive(Copy, Clone)]
+pub struct TransformKey {
+    pub time: Float,
+    pub translation: Vec3<Float>,
+    pub rotation: Vec3<Float>,
+    pub scale: Vec3<Float>
+}
+
+impl TransformKey {
+    pub fn new(time: Float, translation: &Vec3<Float>, rotation: &Vec3<Float>, scale: &Vec3<Float>) -> Self {
+        Self {
+            time,
+            translation: *translation,
+            rotation: *rotation,
+            scale: *scale
+        }
+    }
+}
+
+// The transform at one moment, with the sines and cosines of the rotation worked out
+struct TransformAt {
+    translation: Vec3<Float>,
+    sin: Vec3<Float>,
+    cos: Vec3<Float>,
+    scale: Vec3<Float>
+}
+
+impl TransformAt {
+    fn rotate(&self, v: &Vec3<Float>) -> Vec3<Float> {
+        let (s, c) = (self.sin, self.cos);
+        let v = Vec3::new(v.x(), c.x()*v.y() - s.x()*v.z(), s.x()*v.y() + c.x()*v.z());
+        let v = Vec3::new(c.y()*v.x() + s.y()*v.z(), v.y(), -s.y()*v.x() + c.y()*v.z());
+        Vec3::new(c.z()*v.x() - s.z()*v.y(), s.z()*v.x() + c.z()*v.y(), v.z())
+    }
+
+    fn unrotate(&self, v: &Vec3<Float>) -> Vec3<Float> {
+        let (s, c) = (self.sin, self.cos);
+        let v = Vec3::new(c.z()*v.x() + s.z()*v.y(), -s.z()*v.x() + c.z()*v.y(), v.z());
+        let v = Vec3::new(c.y()*v.x() - s.y()*v.z(), v.y(), s.y()*v.x() + c.y()*v.z());
+        Vec3::new(v.x(), c.x()*v.y() + s.x()*v.z(), -s.x()*v.y() + c.x()*v.z())
+    }
+
+    fn to_world(&self, p: &Vec3<Float>) -> Vec3<Float> {
+        self.rotate(&(*p * self.scale)) + self.translation
+    }
+
+    fn to_local(&self, p: &Vec3<Float>) -> Vec3<Float> {
+        self.direction_to_local(&(*p - self.translation))
+    }
+
+    fn direction_to_local(&self, d: &Vec3<Float>) -> Vec3<Float> {
+        let d = self.unrotate(d);
+        Vec3::new(d.x() / self.scale.x(), d.y() / self.scale.y(), d.z() / self.scale.z())
+    }
+
+    // Normals go through the inverse transpose: rotation times inverse scale
+    fn normal_to_world(&self, n: &Vec3<Float>) -> Vec3<Float> {
+        self.rotate(&Vec3::new(n.x() / self.scale.x(), n.y() / self.scale.y(), n.z() / self.scale.z())).unit_vector()
+    }
+}
+
+pub struct AnimatedTransform {
+    hittable: Arc<dyn Hittable>,
+    times: Vec<Float>,
+    translations: Vec<Vec3<Float>>,
+    rotations: Vec<Vec3<Float>>,
+    scales: Vec<Vec3<Float>>,
+    interpolation: Interpolation
+}
+
+impl AnimatedTransform {
+    // Keys can be in any order, there has to be at least one
+    pub fn new(hittable: Arc<dyn Hittable>, keys: &[TransformKey], interpolation: Interpolation) -> Self {
+        let mut keys = keys.to_vec();
+        keys.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+
+        // Zero scale can't be undone to find the ray in the object's space
+        let safe_scale = |s: Float| if s.abs() < 1e-6 { 1e-6 } else { s };
+
+        Self {
+            hittable,
+            times: keys.iter().map(|k| k.time).collect(),
+            translations: keys.iter().map(|k| k.translation).collect(),
+            rotations: keys.iter().map(|k| k.rotation).collect(),
+            scales: keys.iter().map(|k| Vec3::new(safe_scale(k.scale.x()), safe_scale(k.scale.y()), safe_scale(k.scale.z()))).collect(),
+            interpolation
+        }
+    }
+
+    fn transform_at(&self, time: Float) -> TransformAt {
+        let (i, t) = animation::locate_key(&self.times, time);
+        let rotation = animation::interpolate(&self.rotations, i, t, self.interpolation);
+        let radians = Vec3::new(rotation.x().to_radians(), rotation.y().to_radians(), rotation.z().to_radians());
+
+        TransformAt {
+            translation: animation::interpolate(&self.translations, i, t, self.interpolation),
+            sin: Vec3::new(radians.x().sin(), radians.y().sin(), radians.z().sin()),
+            cos: Vec3::new(radians.x().cos(), radians.y().cos(), radians.z().cos()),
+            scale: animation::interpolate(&self.scales, i, t, self.interpolation)
+        }
+    }
+}
+
+// Steps the time interval is cut into when bounding the moving object
+const BOUNDING_STEPS: usize = 32;
+
+impl Hittable for AnimatedTransform {
+    fn hit(&self, r: &Ray<Float>, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        // The transform is affine, so t means the same along the local ray
+        let transform = self.transform_at(r.time);
+        let local_r = Ray {
+            orig: transform.to_local(&r.orig),
+            dir: transform.direction_to_local(&r.dir),
+            time: r.time,
+            wavelength: r.wavelength
+        };
+
+        let mut hit = self.hittable.hit(&local_r, t_min, t_max)?;
+        let outward_normal = transform.normal_to_world(&if hit.front_facing { hit.normal } else { hit.normal.reverse_dir() });
+        let normal_and_direction = HitRecord::get_normal_and_direction(r, &outward_normal);
+        hit.point = transform.to_world(&hit.point);
+        hit.normal = normal_and_direction.0;
+        hit.front_facing = normal_and_direction.1;
+
+        Some(hit)
+    }
+
+    // The object's box at many moments between time0 and time1 (and at every key in between), all put together.
+    // Rotating corners sweep arcs between the moments, the box is grown by how far an arc can bulge out.
+    fn bounding_box(&self, time0: Float, time1: Float) -> Option<Aabb> {
+        let bbox = self.hittable.bounding_box(time0, time1)?;
+        let mut times: Vec<Float> = (0..=BOUNDING_STEPS).map(|i| time0 + (time1 - time0) * (i as Float) / (BOUNDING_STEPS as Float)).collect();
+        times.extend(self.times.iter().filter(|&&t| t > time0 && t < time1));
+        times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let mut min = Vec3::new(Float::MAX, Float::MAX, Float::MAX);
+        let mut max = Vec3::new(Float::MIN, Float::MIN, Float::MIN);
+        let mut reach: Float = 0.0;
+        let mut max_step_angle: Float = 0.0;
+        let mut last_rotation = Option::None;
+        for &time in times.iter() {
+            let transform = self.transform_at(time);
+            for i in 0..8 {
+                let corner = Vec3::new(
+                    if i & 1 == 0 { bbox.min.x() } else { bbox.max.x() },
+                    if i & 2 == 0 { bbox.min.y() } else { bbox.max.y() },
+                    if i & 4 == 0 { bbox.min.z() } else { bbox.max.z() });
+                let p = transform.to_world(&corner);
+                for c in 0..3 {
+                    min[c] = Float::min(min[c], p[c]);
+                    max[c] = Float::max(max[c], p[c]);
+                }
+                reach = Float::max(reach, (p - transform.translation).length());
+            }
+
+            let (i, t) = animation::locate_key(&self.times, time);
+            let rotation = animation::interpolate(&self.rotations, i, t, self.interpolation);
+            if let Some(last) = last_rotation {
+                let step: Vec3<Float> = rotation - last;
+                max_step_angle = Float::max(max_step_angle, (step.x().abs() + step.y().abs() + step.z().abs()).to_radians());
+            }
+            last_rotation = Some(rotation);
+        }
+
+        let half_step = Float::min(max_step_angle * 0.5, 1.5);
+        let bulge = reach * (1.0 / half_step.cos() - 1.0);
+        let padding = Vec3::new(bulge, bulge, bulge);
+
+        Some(Aabb {
+            min: min - padding,
+            max: max + padding
+        })
+    }
+}
+
+unsafe impl Sync for AnimatedTransform {}
+unsafe impl Send for AnimatedTransform {}
+
 // --------------------------------------------------------------------------------------------------------------------
 // Constant medium
 
@@ -520,17 +727,8 @@ impl ConstantMedium {
 
 impl Hittable for ConstantMedium {
     fn hit(&self, r: &Ray<Float>, t_min: Float, t_max: Float) -> Option<HitRecord> {
-        let hit1_option = self.boundary.hit(&r, Float::MIN, Float::MAX);
-        if hit1_option.is_none() {
-            return Option::None;
-        }
-
-        let mut rec1 = hit1_option.unwrap();
-        let hit2_option = self.boundary.hit(&r, rec1.t + 0.0001, Float::MAX);
-        if hit2_option.is_none() {
-            return Option::None;
-        }
-        let mut rec2 = hit2_option.unwrap();
+        let mut rec1 = self.boundary.hit(&r, Float::MIN, Float::MAX)?;
+        let mut rec2 = self.boundary.hit(&r, rec1.t + 0.0001, Float::MAX)?;
 
         if rec1.t < t_min { rec1.t = t_min };
         if rec2.t > t_max { rec2.t = t_max };
@@ -545,7 +743,9 @@ impl Hittable for ConstantMedium {
 
         let ray_length = r.dir.length();
         let distance_inside_boundary = (rec2.t - rec1.t) * ray_length;
-        let hit_distance = self.neg_inv_density * Float::ln(random_float());
+        // Seeded from the ray itself, so the result doesn't depend on which thread traces it
+        let seed = utils::hash_combine(&[r.orig.x().to_bits(), r.orig.y().to_bits(), r.orig.z().to_bits(), r.dir.x().to_bits(), r.dir.y().to_bits(), r.dir.z().to_bits(), r.time.to_bits()]);
+        let hit_distance = self.neg_inv_density * Float::ln(utils::Rng::new(seed).next_float());
 
         if hit_distance > distance_inside_boundary {
             return Option::None;
@@ -564,7 +764,8 @@ impl Hittable for ConstantMedium {
             point,
             normal,
             front_facing,
-            material: self.phase_function.clone()
+            material: self.phase_function.clone(),
+            object_id: 0
         })
     }
 
@@ -574,4 +775,36 @@ impl Hittable for ConstantMedium {
 }
 
 unsafe impl Sync for ConstantMedium {}
-unsafe impl Send for ConstantMedium {}
\ No newline at end of file
+unsafe impl Send for ConstantMedium {}
+// --------------------------------------------------------------------------------------------------------------------
+// Object tag
+
+pub struct ObjectTag {
+    hittable: Arc<dyn Hittable>,
+    id: u32
+}
+
+impl ObjectTag {
+    pub fn new(hittable: Arc<dyn Hittable>, id: u32) -> Self {
+        Self {
+            hittable,
+            id
+        }
+    }
+}
+
+impl Hittable for ObjectTag {
+    fn hit(&self, r: &Ray<Float>, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let mut hit = self.hittable.hit(r, t_min, t_max)?;
+        hit.object_id = self.id;
+
+        Some(hit)
+    }
+
+    fn bounding_box(&self, time0: Float, time1: Float) -> Option<Aabb> {
+        self.hittable.bounding_box(time0, time1)
+    }
+}
+
+unsafe impl Sync for ObjectTag {}
+unsafe impl Send for ObjectTag {}
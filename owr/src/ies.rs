@@ -0,0 +1,288 @@
+use crate::types::*;
+
+// --------------------------------------------------------------------------------------------------------------------
+// IES LM-63 photometric profile: candela measured on a grid of vertical and horizontal angles around a luminaire.
+//
+// Only type C photometry is supported (nearly every architectural file): vertical angle 0 points straight down the
+// luminaire axis (nadir) and 180 straight up, horizontal angles go around that axis. Files measured with symmetry
+// only list part of the horizontal range, the rest is mirrored when looking up.
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Symmetry {
+    // One horizontal angle: the same in every direction around the axis
+    Rotational,
+    // 0 to 90
+    Quadrant,
+    // 0 to 180
+    Bilateral,
+    // 90 to 270
+    BilateralAcross,
+    // The full circle
+    Full
+}
+
+pub struct IesProfile {
+    vertical_angles: Vec<Float>,
+    horizontal_angles: Vec<Float>,
+    // One row of vertical values per horizontal angle, already scaled by the multipliers in the file
+    candela: Vec<Vec<Float>>,
+    symmetry: Symmetry,
+    max_candela: Float
+}
+
+impl IesProfile {
+    pub fn load(filename: &str) -> Result<Self, String> {
+        let text = std::fs::read(filename).map_err(|e| e.to_string())?;
+        // Older files are often latin-1, none of the numbers care
+        IesProfile::parse(&String::from_utf8_lossy(&text))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        // Keywords and free text up to the TILT line, numbers after it
+        let mut lines = text.lines();
+        let tilt = loop {
+            match lines.next() {
+                Some(line) => {
+                    if let Some(tilt) = line.trim().strip_prefix("TILT=") {
+                        break tilt.trim().to_string();
+                    }
+                }
+                _ => { return Err("Missing TILT= line, not an IES file".to_string()); }
+            }
+        };
+
+        let mut numbers = lines.flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ',')).filter(|s| !s.is_empty());
+        let mut next = || -> Result<Float, String> {
+            let value = numbers.next().ok_or_else(|| "Unexpected end of file".to_string())?;
+            value.parse::<Float>().map_err(|_| format!("Invalid number '{}'", value))
+        };
+
+        // Lamp tilt tables only matter for fixtures mounted at an angle, skip them
+        if tilt == "INCLUDE" {
+            let _lamp_to_luminaire_geometry = next()?;
+            let count = next()? as usize;
+            for _ in 0..(2 * count) {
+                next()?;
+            }
+        }
+
+        let _lamp_count = next()?;
+        let _lumens_per_lamp = next()?;
+        let multiplier = next()?;
+        let vertical_count = next()? as usize;
+        let horizontal_count = next()? as usize;
+        let photometric_type = next()? as i32;
+        let _units = next()?;
+        let (_width, _length, _height) = (next()?, next()?, next()?);
+        let ballast_factor = next()?;
+        let _file_generation_type = next()?;
+        let _input_watts = next()?;
+
+        if photometric_type != 1 {
+            return Err(format!("Photometric type {} is not supported, only type C (1)", photometric_type));
+        }
+        if vertical_count == 0 || horizontal_count == 0 {
+            return Err("No angles in profile".to_string());
+        }
+
+        let vertical_angles = (0..vertical_count).map(|_| next()).collect::<Result<Vec<Float>, String>>()?;
+        let horizontal_angles = (0..horizontal_count).map(|_| next()).collect::<Result<Vec<Float>, String>>()?;
+        let mut candela = Vec::with_capacity(horizontal_count);
+        for _ in 0..horizontal_count {
+            let row = (0..vertical_count).map(|_| next().map(|c| c * multiplier * ballast_factor)).collect::<Result<Vec<Float>, String>>()?;
+            candela.push(row);
+        }
+
+        let first = horizontal_angles[0];
+        let last = horizontal_angles[horizontal_count - 1];
+        let symmetry = if horizontal_count == 1 {
+            Symmetry::Rotational
+        } else if first == 90.0 && last == 270.0 {
+            Symmetry::BilateralAcross
+        } else if last == 90.0 {
+            Symmetry::Quadrant
+        } else if last == 180.0 {
+            Symmetry::Bilateral
+        } else {
+            Symmetry::Full
+        };
+
+        let max_candela = candela.iter().flatten().fold(0.0, |max: Float, &c| max.max(c));
+
+        Ok(Self {
+            vertical_angles,
+            horizontal_angles,
+            candela,
+            symmetry,
+            max_candela
+        })
+    }
+
+    pub fn max_candela(&self) -> Float {
+        self.max_candela
+    }
+
+    // Candela towards the given angles (degrees), bilinearly interpolated. Zero outside the measured vertical range.
+    pub fn candela(&self, vertical: Float, horizontal: Float) -> Float {
+        let (v, v_t) = match locate(&self.vertical_angles, vertical) {
+            Some(location) => { location }
+            _ => { return 0.0; }
+        };
+
+        let row = |h: usize| {
+            let values = &self.candela[h];
+            values[v] + (values[usize::min(v + 1, values.len() - 1)] - values[v]) * v_t
+        };
+
+        if self.symmetry == Symmetry::Rotational {
+            return row(0);
+        }
+
+        // Fold the full circle onto the part that was measured
+        let mut h = horizontal.rem_euclid(360.0);
+        match self.symmetry {
+            Symmetry::Quadrant => {
+                if h > 180.0 { h = 360.0 - h; }
+                if h > 90.0 { h = 180.0 - h; }
+            }
+            Symmetry::Bilateral if h > 180.0 => {
+                h = 360.0 - h;
+            }
+            Symmetry::BilateralAcross => {
+                if h < 90.0 { h = 180.0 - h; } else if h > 270.0 { h = 540.0 - h; }
+            }
+            _ => {}
+        }
+
+        let last = self.horizontal_angles.len() - 1;
+        match locate(&self.horizontal_angles, h) {
+            Some((i, t)) => { row(i) + (row(usize::min(i + 1, last)) - row(i)) * t }
+            _ if self.symmetry == Symmetry::Full => {
+                // Between the last measured angle and the first one, around the circle
+                let first_angle = self.horizontal_angles[0] + 360.0;
+                let h = if h < self.horizontal_angles[0] { h + 360.0 } else { h };
+                let t = (h - self.horizontal_angles[last]) / (first_angle - self.horizontal_angles[last]);
+                row(last) + (row(0) - row(last)) * t
+            }
+            _ => { 0.0 }
+        }
+    }
+}
+
+// Index of the interval holding x in the sorted angles, and how far along it is
+fn locate(angles: &[Float], x: Float) -> Option<(usize, Float)> {
+    let first = angles[0];
+    let last = angles[angles.len() - 1];
+    if x < first || x > last {
+        return Option::None;
+    }
+    if angles.len() == 1 || x == last {
+        return Some((angles.len() - 1, 0.0));
+    }
+
+    let i = usize::min(angles.partition_point(|&a| a <= x), angles.len() - 1) - 1;
+    let span = angles[i + 1] - angles[i];
+
+    Some((i, if span > 0.0 { (x - angles[i]) / span } else { 0.0 }))
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: Float, b: Float) {
+        assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn parses_rotational_profile_over_several_lines() {
+        let text = "IESNA:LM-63-2002\n\
+            [TEST] rotational\n\
+            [MANUFAC] nobody\n\
+            TILT=NONE\n\
+            1 1000 2.0 5 1 1 1 0.1 0.1 0.0\n\
+            0.5 1 60\n\
+            0 22.5 45\n\
+            67.5, 90\n\
+            0\n\
+            100 90 80\n\
+            70\n\
+            60\n";
+        let profile = IesProfile::parse(text).unwrap();
+
+        // Multiplier and ballast factor cancel out
+        assert_eq!(profile.symmetry, Symmetry::Rotational);
+        assert_near(profile.max_candela(), 100.0);
+        assert_near(profile.candela(0.0, 0.0), 100.0);
+        assert_near(profile.candela(45.0, 123.0), 80.0);
+        assert_near(profile.candela(33.75, 300.0), 85.0);
+        assert_near(profile.candela(90.0, 0.0), 60.0);
+        assert_near(profile.candela(120.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn expands_quadrant_symmetry() {
+        let text = "IESNA:LM-63-1995\n\
+            TILT=NONE\n\
+            1 -1 1 2 2 1 1 0 0 0\n\
+            1 1 10\n\
+            0 90\n\
+            0 90\n\
+            100 50\n\
+            200 20\n";
+        let profile = IesProfile::parse(text).unwrap();
+
+        assert_eq!(profile.symmetry, Symmetry::Quadrant);
+        assert_near(profile.candela(0.0, 0.0), 100.0);
+        assert_near(profile.candela(0.0, 90.0), 200.0);
+        assert_near(profile.candela(0.0, 45.0), 150.0);
+        assert_near(profile.candela(0.0, 180.0), 100.0);
+        assert_near(profile.candela(0.0, 270.0), 200.0);
+        assert_near(profile.candela(0.0, 135.0), 150.0);
+        assert_near(profile.candela(90.0, 315.0), 35.0);
+        assert_near(profile.candela(0.0, -90.0), 200.0);
+    }
+
+    #[test]
+    fn expands_bilateral_symmetry() {
+        let text = "TILT=NONE\n\
+            1 -1 1 1 3 1 1 0 0 0\n\
+            1 1 10\n\
+            0\n\
+            0 90 180\n\
+            10 20 30\n";
+        let profile = IesProfile::parse(text).unwrap();
+
+        assert_eq!(profile.symmetry, Symmetry::Bilateral);
+        assert_near(profile.candela(0.0, 90.0), 20.0);
+        assert_near(profile.candela(0.0, 270.0), 20.0);
+        assert_near(profile.candela(0.0, 300.0), 50.0 / 3.0);
+        assert_near(profile.candela(0.0, 180.0), 30.0);
+    }
+
+    #[test]
+    fn skips_included_tilt_table() {
+        let text = "TILT=INCLUDE\n\
+            1\n\
+            3\n\
+            0 45 90\n\
+            1 0.9 0.8\n\
+            1 -1 1 1 1 1 1 0 0 0\n\
+            1 1 10\n\
+            0\n\
+            0\n\
+            42\n";
+        let profile = IesProfile::parse(text).unwrap();
+
+        assert_near(profile.candela(0.0, 0.0), 42.0);
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        assert!(IesProfile::parse("no tilt line\n1 2 3\n").is_err());
+        assert!(IesProfile::parse("TILT=NONE\n1 -1 1 1 1 2 1 0 0 0\n1 1 10\n0\n0\n42\n").is_err());
+        assert!(IesProfile::parse("TILT=NONE\n1 -1 1 2 1 1 1 0 0 0\n1 1 10\n0 90\n0\n42\n").is_err());
+    }
+}
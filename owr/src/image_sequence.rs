@@ -0,0 +1,115 @@
+use crate::types::*;
+
+use std::io::Write;
+extern crate image;
+
+// --------------------------------------------------------------------------------------------------------------------
+// Animated images from rendered frames, looping forever at the given frame rate
+
+// Animated GIF, colors are quantized per frame
+pub fn write_gif(filename: &str, frames: &[image::RgbaImage], fps: Float) -> Result<(), String> {
+    let file = std::fs::File::create(filename).map_err(|e| e.to_string())?;
+    let mut encoder = image::codecs::gif::GifEncoder::new_with_speed(std::io::BufWriter::new(file), 10);
+    encoder.set_repeat(image::codecs::gif::Repeat::Infinite).map_err(|e| e.to_string())?;
+
+    let delay = image::Delay::from_numer_denom_ms((1000.0 / fps.max(0.001)).round() as u32, 1);
+    for frame in frames {
+        encoder.encode_frame(image::Frame::from_parts(frame.clone(), 0, 0, delay)).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+// Animated PNG (APNG). Every frame is encoded by the png encoder on its own, then its image data is put in the frame
+// chunks of one file (acTL, fcTL and fdAT, see the APNG specification). Viewers without APNG support show frame 0.
+pub fn write_apng(filename: &str, frames: &[image::RgbaImage], fps: Float) -> Result<(), String> {
+    if frames.is_empty() {
+        return Err("No frames to write".to_string());
+    }
+
+    let (width, height) = frames[0].dimensions();
+    let (delay_numerator, delay_denominator) = ((1000.0 / fps.max(0.001)).round() as u16, 1000u16);
+
+    let mut out = Vec::new();
+    out.extend_from_slice(b"\x89PNG\r\n\x1a\n");
+
+    let mut sequence = 0u32;
+    for (i, frame) in frames.iter().enumerate() {
+        let mut png = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut png).encode(frame.as_raw(), width, height, image::ColorType::Rgba8).map_err(|e| e.to_string())?;
+        let chunks = png_chunks(&png)?;
+
+        if i == 0 {
+            let ihdr = chunks.iter().find(|chunk| &chunk.0 == b"IHDR").ok_or_else(|| "Missing IHDR".to_string())?;
+            write_chunk(&mut out, b"IHDR", &ihdr.1);
+
+            let mut actl = Vec::new();
+            actl.extend_from_slice(&(frames.len() as u32).to_be_bytes());
+            actl.extend_from_slice(&0u32.to_be_bytes());
+            write_chunk(&mut out, b"acTL", &actl);
+        }
+
+        // Frame control: size, offset, delay, no dispose, no blending
+        let mut fctl = Vec::new();
+        fctl.extend_from_slice(&sequence.to_be_bytes());
+        fctl.extend_from_slice(&width.to_be_bytes());
+        fctl.extend_from_slice(&height.to_be_bytes());
+        fctl.extend_from_slice(&0u32.to_be_bytes());
+        fctl.extend_from_slice(&0u32.to_be_bytes());
+        fctl.extend_from_slice(&delay_numerator.to_be_bytes());
+        fctl.extend_from_slice(&delay_denominator.to_be_bytes());
+        fctl.extend_from_slice(&[0, 0]);
+        write_chunk(&mut out, b"fcTL", &fctl);
+        sequence += 1;
+
+        // The first frame is the default image, the others go in fdAT chunks with a sequence number in front
+        for (_, data) in chunks.iter().filter(|chunk| &chunk.0 == b"IDAT") {
+            if i == 0 {
+                write_chunk(&mut out, b"IDAT", data);
+            } else {
+                let mut fdat = Vec::with_capacity(data.len() + 4);
+                fdat.extend_from_slice(&sequence.to_be_bytes());
+                fdat.extend_from_slice(data);
+                write_chunk(&mut out, b"fdAT", &fdat);
+                sequence += 1;
+            }
+        }
+    }
+    write_chunk(&mut out, b"IEND", &[]);
+
+    let mut file = std::fs::File::create(filename).map_err(|e| e.to_string())?;
+    file.write_all(&out).map_err(|e| e.to_string())
+}
+
+// Type and data of a png chunk
+type PngChunk = ([u8; 4], Vec<u8>);
+
+// Every chunk in a png file
+fn png_chunks(png: &[u8]) -> Result<Vec<PngChunk>, String> {
+    let mut chunks = Vec::new();
+    let mut offset = 8;
+    while offset + 12 <= png.len() {
+        let length = u32::from_be_bytes([png[offset], png[offset + 1], png[offset + 2], png[offset + 3]]) as usize;
+        let end = offset + 12 + length;
+        if end > png.len() {
+            return Err("Truncated png chunk".to_string());
+        }
+
+        let chunk_type = [png[offset + 4], png[offset + 5], png[offset + 6], png[offset + 7]];
+        chunks.push((chunk_type, png[(offset + 8)..(offset + 8 + length)].to_vec()));
+        offset = end;
+    }
+
+    Ok(chunks)
+}
+
+fn write_chunk(out: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(chunk_type);
+    crc.update(data);
+
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(chunk_type);
+    out.extend_from_slice(data);
+    out.extend_from_slice(&crc.finalize().to_be_bytes());
+}
@@ -5,6 +5,7 @@ pub mod hittable;
 pub mod sphere;
 pub mod camera;
 pub mod material;
+pub mod microfacet;
 pub mod utils;
 pub mod examples;
 pub mod types;
@@ -12,4 +13,17 @@ pub mod sampling;
 pub mod aabb;
 pub mod bvh;
 pub mod texture;
-pub mod perlin;
\ No newline at end of file
+pub mod perlin;
+pub mod aov;
+pub mod filter;
+pub mod film;
+pub mod sampler;
+pub mod distribution;
+pub mod light;
+pub mod environment;
+pub mod background;
+pub mod scene;
+pub mod sky;
+pub mod ies;
+pub mod animation;
+pub mod image_sequence;
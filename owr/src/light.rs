@@ -0,0 +1,325 @@
+use crate::types::*;
+use crate::vec3::Vec3;
+use crate::utils;
+use crate::ies::IesProfile;
+
+use std::sync::Arc;
+
+// --------------------------------------------------------------------------------------------------------------------
+// Light sample: a direction from the shading point towards the light and what arrives along it
+
+pub struct LightSample {
+    pub direction: Vec3<Float>,
+    pub distance: Float,
+    pub radiance: Vec3<Float>,
+    pub pdf: Float,
+    pub is_delta: bool
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Light trait, for lights that are sampled directly (next event estimation)
+
+pub trait Light: Sync + Send {
+    // Picks a direction towards the light, the pdf is with respect to solid angle at the point.
+    // Lights at infinity report an infinite distance.
+    fn sample(&self, point: &Vec3<Float>, u: (Float, Float)) -> Option<LightSample>;
+
+    // Pdf of sample() returning this direction. Only lights that scattered rays can also
+    // hit by chance need this, so their contributions can be combined with multiple importance sampling.
+    fn pdf(&self, _point: &Vec3<Float>, _direction: &Vec3<Float>) -> Float { 0.0 }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// How the light from a point or spot light gets weaker with distance. Quadratic is physically correct,
+// the others are there for artistic control.
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Falloff {
+    Constant,
+    Linear,
+    Quadratic
+}
+
+impl Default for Falloff {
+    fn default() -> Self { Falloff::Quadratic }
+}
+
+impl Falloff {
+    pub fn from_name(name: &str) -> Option<Falloff> {
+        match name {
+            "constant" | "none" => { Some(Falloff::Constant) }
+            "linear" => { Some(Falloff::Linear) }
+            "quadratic" | "inverse-square" => { Some(Falloff::Quadratic) }
+            _ => { Option::None }
+        }
+    }
+
+    pub fn attenuation(&self, distance: Float) -> Float {
+        match self {
+            Falloff::Constant => { 1.0 }
+            Falloff::Linear => { 1.0 / distance }
+            Falloff::Quadratic => { 1.0 / (distance * distance) }
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Point light, radiating intensity (W/sr, per channel) equally in all directions
+
+pub struct PointLight {
+    pub position: Vec3<Float>,
+    pub intensity: Vec3<Float>,
+    pub falloff: Falloff
+}
+
+impl PointLight {
+    pub fn new(position: &Vec3<Float>, intensity: &Vec3<Float>) -> Self {
+        Self {
+            position: *position,
+            intensity: *intensity,
+            falloff: Falloff::default()
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, point: &Vec3<Float>, _u: (Float, Float)) -> Option<LightSample> {
+        let to_light = self.position - *point;
+        let distance = to_light.length();
+        if distance == 0.0 {
+            return Option::None;
+        }
+
+        Some(LightSample {
+            direction: to_light * (1.0 / distance),
+            distance,
+            radiance: self.intensity * self.falloff.attenuation(distance),
+            pdf: 1.0,
+            is_delta: true
+        })
+    }
+}
+
+unsafe impl Sync for PointLight {}
+unsafe impl Send for PointLight {}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Spot light: a point light limited to a cone. Full intensity inside the inner angle, fading smoothly
+// to nothing at the outer angle (half angles, degrees).
+
+pub struct SpotLight {
+    pub position: Vec3<Float>,
+    pub direction: Vec3<Float>,
+    pub intensity: Vec3<Float>,
+    pub falloff: Falloff,
+    cos_inner: Float,
+    cos_outer: Float
+}
+
+impl SpotLight {
+    pub fn new(position: &Vec3<Float>, target: &Vec3<Float>, intensity: &Vec3<Float>, outer_angle: Float, inner_angle: Float) -> Self {
+        let outer_angle = outer_angle.clamp(0.0, 180.0);
+        let inner_angle = inner_angle.clamp(0.0, outer_angle);
+
+        Self {
+            position: *position,
+            direction: (*target - *position).unit_vector(),
+            intensity: *intensity,
+            falloff: Falloff::default(),
+            cos_inner: inner_angle.to_radians().cos(),
+            cos_outer: outer_angle.to_radians().cos()
+        }
+    }
+
+}
+
+impl Light for SpotLight {
+    fn sample(&self, point: &Vec3<Float>, _u: (Float, Float)) -> Option<LightSample> {
+        let to_light = self.position - *point;
+        let distance = to_light.length();
+        if distance == 0.0 {
+            return Option::None;
+        }
+
+        let direction = to_light * (1.0 / distance);
+        let cone = spot_cone(-direction.dot(&self.direction), self.cos_inner, self.cos_outer);
+        if cone == 0.0 {
+            return Option::None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: self.intensity * (cone * self.falloff.attenuation(distance)),
+            pdf: 1.0,
+            is_delta: true
+        })
+    }
+}
+
+unsafe impl Sync for SpotLight {}
+unsafe impl Send for SpotLight {}
+
+// 1 inside the inner cone, 0 outside the outer one, smoothstep in between
+fn spot_cone(cos_angle: Float, cos_inner: Float, cos_outer: Float) -> Float {
+    if cos_angle >= cos_inner {
+        return 1.0;
+    }
+    if cos_angle <= cos_outer {
+        return 0.0;
+    }
+
+    let t = (cos_angle - cos_outer) / (cos_inner - cos_outer);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Goniometric light: a point light whose intensity in every direction comes from a measured (IES) profile, times
+// the given color/scale. The profile's nadir (vertical angle 0) points along the direction, horizontal angle 0 is
+// the first axis of utils::orthonormal_basis around it (+x for a light aimed straight down). As a spot it is also
+// limited to a cone like SpotLight.
+
+pub struct GoniometricLight {
+    pub position: Vec3<Float>,
+    pub direction: Vec3<Float>,
+    pub intensity: Vec3<Float>,
+    pub falloff: Falloff,
+    profile: Arc<IesProfile>,
+    cone: Option<(Float, Float)>
+}
+
+impl GoniometricLight {
+    // Hanging straight down
+    pub fn new_point(position: &Vec3<Float>, profile: Arc<IesProfile>, intensity: &Vec3<Float>) -> Self {
+        Self {
+            position: *position,
+            direction: Vec3::new(0.0, -1.0, 0.0),
+            intensity: *intensity,
+            falloff: Falloff::default(),
+            profile,
+            cone: Option::None
+        }
+    }
+
+    // Aimed at the target, with an optional cone (outer and inner half angles in degrees)
+    pub fn new_spot(position: &Vec3<Float>, target: &Vec3<Float>, profile: Arc<IesProfile>, intensity: &Vec3<Float>, cone: Option<(Float, Float)>) -> Self {
+        Self {
+            position: *position,
+            direction: (*target - *position).unit_vector(),
+            intensity: *intensity,
+            falloff: Falloff::default(),
+            profile,
+            cone: cone.map(|(outer, inner)| {
+                let outer = outer.clamp(0.0, 180.0);
+                (inner.clamp(0.0, outer).to_radians().cos(), outer.to_radians().cos())
+            })
+        }
+    }
+
+    // Candela in the given direction away from the light
+    fn candela(&self, emitted: &Vec3<Float>) -> Float {
+        let (tangent, bitangent) = utils::orthonormal_basis(&self.direction);
+        let vertical = emitted.dot(&self.direction).clamp(-1.0, 1.0).acos().to_degrees();
+        let horizontal = Float::atan2(emitted.dot(&bitangent), emitted.dot(&tangent)).to_degrees();
+
+        self.profile.candela(vertical, horizontal)
+    }
+}
+
+impl Light for GoniometricLight {
+    fn sample(&self, point: &Vec3<Float>, _u: (Float, Float)) -> Option<LightSample> {
+        let to_light = self.position - *point;
+        let distance = to_light.length();
+        if distance == 0.0 {
+            return Option::None;
+        }
+
+        let direction = to_light * (1.0 / distance);
+        let emitted = direction.reverse_dir();
+        let cone = match self.cone {
+            Some((cos_inner, cos_outer)) => { spot_cone(emitted.dot(&self.direction), cos_inner, cos_outer) }
+            _ => { 1.0 }
+        };
+
+        let candela = cone * self.candela(&emitted);
+        if candela <= 0.0 {
+            return Option::None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: self.intensity * (candela * self.falloff.attenuation(distance)),
+            pdf: 1.0,
+            is_delta: true
+        })
+    }
+}
+
+unsafe impl Sync for GoniometricLight {}
+unsafe impl Send for GoniometricLight {}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Directional light, like the sun: light arriving from one direction everywhere in the scene, with the given
+// irradiance on a surface facing it. A non-zero angular diameter (degrees) makes it a small disc in the sky
+// with soft shadows, 0.53 for the real sun.
+
+pub struct DirectionalLight {
+    pub direction: Vec3<Float>,
+    pub irradiance: Vec3<Float>,
+    cos_max: Float
+}
+
+impl DirectionalLight {
+    // The direction is the way the light travels, e.g. (0, -1, 0) for light straight from above
+    pub fn new(direction: &Vec3<Float>, irradiance: &Vec3<Float>, angular_diameter: Float) -> Self {
+        Self {
+            direction: direction.unit_vector(),
+            irradiance: *irradiance,
+            cos_max: (0.5 * angular_diameter.clamp(0.0, 180.0)).to_radians().cos()
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _point: &Vec3<Float>, u: (Float, Float)) -> Option<LightSample> {
+        let towards_light = self.direction.reverse_dir();
+        if self.cos_max >= 1.0 {
+            return Some(LightSample {
+                direction: towards_light,
+                distance: Float::INFINITY,
+                radiance: self.irradiance,
+                pdf: 1.0,
+                is_delta: true
+            });
+        }
+
+        // Uniform over the disc, with radiance such that the whole disc gives the same irradiance
+        let solid_angle = 2.0 * (std::f32::consts::PI as Float) * (1.0 - self.cos_max);
+        let direction = utils::local_to_world(&utils::cone_vec3_from(u, self.cos_max), &towards_light);
+
+        Some(LightSample {
+            direction,
+            distance: Float::INFINITY,
+            radiance: self.irradiance * (1.0 / solid_angle),
+            pdf: 1.0 / solid_angle,
+            is_delta: false
+        })
+    }
+}
+
+unsafe impl Sync for DirectionalLight {}
+unsafe impl Send for DirectionalLight {}
+
+// --------------------------------------------------------------------------------------------------------------------
+
+// Multiple importance sampling weight for a sample taken with pdf_a, when pdf_b could have produced it too
+pub fn power_heuristic(pdf_a: Float, pdf_b: Float) -> Float {
+    let a = pdf_a * pdf_a;
+    let b = pdf_b * pdf_b;
+    if a + b == 0.0 { 0.0 } else { a / (a + b) }
+}
+
+pub fn luminance(color: &Vec3<Float>) -> Float {
+    0.2126 * color[0] + 0.7152 * color[1] + 0.0722 * color[2]
+}
@@ -1,9 +1,13 @@
 use crate::ray::{Ray};
 use crate::hittable::{HitRecord};
 use crate::types::*;
-use crate::utils::{self, random_in_unit_sphere};
+use crate::utils;
+use crate::sampler::Sampler;
 use crate::texture::*;
 use crate::vec3::Vec3;
+use crate::light;
+use crate::color;
+use crate::microfacet::{self, Frame, Ggx};
 
 use std::sync::Arc;
 
@@ -15,12 +19,35 @@ pub struct ScatterResult {
     pub attenuation: Vec3<Float>
 }
 
+// --------------------------------------------------------------------------------------------------------------------
+// How a ray came to hit a surface: straight from the camera, scattered off a diffuse-like surface (anything that's
+// light sampled), or off a specular one (mirrors, glass)
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum RayKind {
+    Camera,
+    Diffuse,
+    Specular
+}
+
 // --------------------------------------------------------------------------------------------------------------------
 // Material trait
 
 pub trait Material: Sync + Send {
-    fn scatter(&self, r_in : &Ray<Float>, hit: &HitRecord) -> Option<ScatterResult>;
-    fn emitted(&self, _u: Float, _v: Float, _p: &Vec3<Float>) -> Vec3<Float> { Vec3::default() }
+    fn scatter(&self, r_in : &Ray<Float>, hit: &HitRecord, sampler: &mut dyn Sampler) -> Option<ScatterResult>;
+    fn emitted(&self, _hit: &HitRecord) -> Vec3<Float> { Vec3::default() }
+
+    // Surfaces that rays of some kind don't see at all (lights hidden from the camera) let them through
+    fn visible(&self, _kind: RayKind) -> bool { true }
+
+    // For light sampling: bsdf times cosine for light arriving from direction, and the pdf of scatter() picking
+    // that direction. Materials that keep the defaults (perfect mirrors, glass) are never light sampled.
+    fn eval(&self, _r_in : &Ray<Float>, _hit: &HitRecord, _direction: &Vec3<Float>) -> Vec3<Float> { Vec3::default() }
+    fn scattering_pdf(&self, _r_in : &Ray<Float>, _hit: &HitRecord, _direction: &Vec3<Float>) -> Float { 0.0 }
+
+    // Whether light sampling can find any of the light this material scatters, however the scattered ray was picked
+    // (a transmissive Principled is lit through its reflection lobes even when it refracted the ray)
+    fn has_non_specular_lobe(&self) -> bool { false }
 }
 
 // --------------------------------------------------------------------------------------------------------------------
@@ -39,17 +66,29 @@ impl Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, r_in : &Ray<Float>, hit: &HitRecord) -> Option<ScatterResult> {
-        let mut scatter_direction = hit.normal + utils::random_unit_vec3();
+    fn scatter(&self, r_in : &Ray<Float>, hit: &HitRecord, sampler: &mut dyn Sampler) -> Option<ScatterResult> {
+        let mut scatter_direction = hit.normal + utils::unit_vec3_from(sampler.get_2d());
         if utils::near_zero(&scatter_direction) {
             scatter_direction = hit.normal;
         }
 
         Some(ScatterResult {
-            scattered: Ray { orig: hit.point, dir: scatter_direction, time: r_in.time },
+            scattered: Ray { orig: hit.point, dir: scatter_direction, time: r_in.time, wavelength: r_in.wavelength },
             attenuation: self.albedo.value(hit.u, hit.v, &hit.point)
         })
     }
+
+    fn eval(&self, r_in : &Ray<Float>, hit: &HitRecord, direction: &Vec3<Float>) -> Vec3<Float> {
+        self.albedo.value(hit.u, hit.v, &hit.point) * self.scattering_pdf(r_in, hit, direction)
+    }
+
+    // Scattered directions are cosine distributed around the normal
+    fn scattering_pdf(&self, _r_in : &Ray<Float>, hit: &HitRecord, direction: &Vec3<Float>) -> Float {
+        let cosine = hit.normal.dot(&direction.unit_vector());
+        Float::max(cosine, 0.0) / (std::f32::consts::PI as Float)
+    }
+
+    fn has_non_specular_lobe(&self) -> bool { true }
 }
 
 unsafe impl Sync for Lambertian {}
@@ -64,12 +103,12 @@ pub struct Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, r_in : &Ray<Float>, hit: &HitRecord) -> Option<ScatterResult> { 
+    fn scatter(&self, r_in : &Ray<Float>, hit: &HitRecord, sampler: &mut dyn Sampler) -> Option<ScatterResult> { 
         let reflected = utils::reflect(&r_in.dir.unit_vector(), &hit.normal);
         let mut return_option = Option::None;
         if reflected.dot(&hit.normal) > 0.0 {
             return_option = Some(ScatterResult {
-                scattered: Ray { orig: hit.point, dir: reflected + (utils::random_in_unit_sphere() * self.fuzz), time: r_in.time},
+                scattered: Ray { orig: hit.point, dir: reflected + (utils::in_unit_sphere_from(sampler.get_2d(), sampler.get_1d()) * self.fuzz), time: r_in.time, wavelength: r_in.wavelength },
                 attenuation: self.albedo
             })
         }
@@ -82,13 +121,220 @@ unsafe impl Sync for Metal {}
 unsafe impl Send for Metal {}
 
 // --------------------------------------------------------------------------------------------------------------------
-// Dielectric
+// Conductor: rough metal made of GGX microfacets, each a perfect mirror, with the visible ones sampled so no rays are
+// wasted below the surface. How much a microfacet reflects comes from either an albedo, the reflectance straight on
+// going to white at grazing angles (Schlick), or the metal's complex index of refraction, which also gets the color
+// shifts at grazing angles right (see MetalPreset for measured ones).
+// Roughness and anisotropy are read from the first channel of their textures; anisotropic highlights are stretched
+// along the part of `tangent` that lies in the surface (around the y axis by default, like a brushed pot).
+
+pub enum ConductorFresnel {
+    Schlick(Arc<dyn Texture>),
+    // eta and k, per color channel
+    ComplexIor(Vec3<Float>, Vec3<Float>)
+}
+
+// Measured metals, complex index of refraction at red, green and blue wavelengths
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MetalPreset {
+    Gold,
+    Silver,
+    Copper,
+    Aluminium,
+    Chrome,
+    Iron
+}
+
+impl MetalPreset {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "gold" => { Some(MetalPreset::Gold) }
+            "silver" => { Some(MetalPreset::Silver) }
+            "copper" => { Some(MetalPreset::Copper) }
+            "aluminium" | "aluminum" => { Some(MetalPreset::Aluminium) }
+            "chrome" => { Some(MetalPreset::Chrome) }
+            "iron" => { Some(MetalPreset::Iron) }
+            _ => { Option::None }
+        }
+    }
+
+    // (eta, k)
+    pub fn ior(&self) -> (Vec3<Float>, Vec3<Float>) {
+        match self {
+            MetalPreset::Gold => { (Vec3::new(0.143119, 0.374957, 1.44248), Vec3::new(3.98316, 2.38572, 1.60322)) }
+            MetalPreset::Silver => { (Vec3::new(0.155265, 0.116723, 0.138342), Vec3::new(4.82835, 3.12225, 2.14696)) }
+            MetalPreset::Copper => { (Vec3::new(0.200438, 0.924033, 1.10221), Vec3::new(3.91295, 2.45285, 2.14219)) }
+            MetalPreset::Aluminium => { (Vec3::new(1.65746, 0.880369, 0.521229), Vec3::new(9.22387, 6.26952, 4.837)) }
+            MetalPreset::Chrome => { (Vec3::new(4.36968, 2.9167, 1.6547), Vec3::new(5.20643, 4.23136, 3.75495)) }
+            MetalPreset::Iron => { (Vec3::new(2.9114, 2.9497, 2.5845), Vec3::new(3.0893, 2.9318, 2.767)) }
+        }
+    }
+}
+
+pub struct Conductor {
+    pub fresnel: ConductorFresnel,
+    pub roughness: Arc<dyn Texture>,
+    pub anisotropy: Arc<dyn Texture>,
+    pub tangent: Vec3<Float>
+}
+
+impl Conductor {
+    pub fn new(albedo: Arc<dyn Texture>, roughness: Arc<dyn Texture>) -> Self {
+        Self {
+            fresnel: ConductorFresnel::Schlick(albedo),
+            roughness,
+            anisotropy: Arc::new(SolidColor::new_scalar(0.0)),
+            tangent: Vec3::new(0.0, 1.0, 0.0)
+        }
+    }
+
+    pub fn new_from_color(color: &Vec3<Float>, roughness: Float) -> Self {
+        Self::new(Arc::new(SolidColor::new(color)), Arc::new(SolidColor::new_scalar(roughness)))
+    }
+
+    pub fn new_from_ior(eta: &Vec3<Float>, k: &Vec3<Float>, roughness: Arc<dyn Texture>) -> Self {
+        let mut conductor = Self::new(Arc::new(SolidColor::new_scalar(1.0)), roughness);
+        conductor.fresnel = ConductorFresnel::ComplexIor(*eta, *k);
+        conductor
+    }
+
+    pub fn new_from_preset(preset: MetalPreset, roughness: Float) -> Self {
+        let (eta, k) = preset.ior();
+        Self::new_from_ior(&eta, &k, Arc::new(SolidColor::new_scalar(roughness)))
+    }
+
+    // Shading frame, microfacet distribution and the direction the ray came from in that frame
+    fn at(&self, r_in : &Ray<Float>, hit: &HitRecord) -> (Frame, Ggx, Vec3<Float>) {
+        let roughness = self.roughness.value(hit.u, hit.v, &hit.point).x().clamp(0.0, 1.0);
+        let anisotropy = self.anisotropy.value(hit.u, hit.v, &hit.point).x();
+        let frame = Frame::new_with_tangent(&hit.normal, &self.tangent);
+        let wo = frame.to_local(&r_in.dir.unit_vector().reverse_dir());
+
+        (frame, Ggx::from_roughness_anisotropic(roughness, anisotropy), wo)
+    }
+
+    fn fresnel(&self, hit: &HitRecord, cos_theta: Float) -> Vec3<Float> {
+        match &self.fresnel {
+            ConductorFresnel::Schlick(albedo) => { microfacet::fresnel_schlick(&albedo.value(hit.u, hit.v, &hit.point), cos_theta) }
+            ConductorFresnel::ComplexIor(eta, k) => { microfacet::fresnel_conductor(cos_theta, eta, k) }
+        }
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(&self, r_in : &Ray<Float>, hit: &HitRecord, sampler: &mut dyn Sampler) -> Option<ScatterResult> {
+        let (frame, distribution, wo) = self.at(r_in, hit);
+        if wo.z() <= 0.0 {
+            return Option::None;
+        }
+
+        let wh = distribution.sample_wh(&wo, sampler.get_2d());
+        let wi = microfacet::reflect(&wo, &wh);
+        if wi.z() <= 0.0 {
+            return Option::None;
+        }
+
+        // bsdf * cos / pdf, most of it cancels out
+        Some(ScatterResult {
+            scattered: Ray { orig: hit.point, dir: frame.to_world(&wi), time: r_in.time, wavelength: r_in.wavelength },
+            attenuation: self.fresnel(hit, wo.dot(&wh)) * (distribution.g(&wo, &wi) / distribution.g1(&wo))
+        })
+    }
+
+    fn eval(&self, r_in : &Ray<Float>, hit: &HitRecord, direction: &Vec3<Float>) -> Vec3<Float> {
+        let (frame, distribution, wo) = self.at(r_in, hit);
+        let wi = frame.to_local(&direction.unit_vector());
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Vec3::default();
+        }
+
+        let wh = (wo + wi).unit_vector();
+        self.fresnel(hit, wo.dot(&wh)) * (distribution.d(&wh) * distribution.g(&wo, &wi) / (4.0 * wo.z()))
+    }
+
+    fn scattering_pdf(&self, r_in : &Ray<Float>, hit: &HitRecord, direction: &Vec3<Float>) -> Float {
+        let (frame, distribution, wo) = self.at(r_in, hit);
+        let wi = frame.to_local(&direction.unit_vector());
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let wh = (wo + wi).unit_vector();
+        distribution.pdf(&wo, &wh) / (4.0 * wo.dot(&wh))
+    }
+
+    fn has_non_specular_lobe(&self) -> bool { true }
+}
+
+unsafe impl Sync for Conductor {}
+unsafe impl Send for Conductor {}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Index of refraction that changes with the wavelength (in nm), which splits white light into a rainbow. Both models
+// take the wavelength in micrometers: Cauchy's n = a + b / lambda^2, and Sellmeier's
+// n^2 = 1 + sum of b_i lambda^2 / (lambda^2 - c_i), with c_i in micrometers squared.
+
+#[derive(Copy, Clone, Debug)]
+pub enum Dispersion {
+    Cauchy(Float, Float),
+    Sellmeier([Float; 3], [Float; 3])
+}
+
+impl Dispersion {
+    // Common crown glass (Schott N-BK7), n = 1.517 for yellow light
+    pub fn crown_glass() -> Self {
+        Dispersion::Sellmeier([1.039612, 0.2317923, 1.010469], [0.006000699, 0.02001791, 103.5607])
+    }
+
+    // Dense flint glass (Schott SF11), n = 1.785, for prisms
+    pub fn flint_glass() -> Self {
+        Dispersion::Sellmeier([1.737597, 0.3137473, 1.898781], [0.01318871, 0.06230681, 155.2363])
+    }
+
+    // n = 2.42, the fire of a cut diamond
+    pub fn diamond() -> Self {
+        Dispersion::Sellmeier([0.3306, 4.3356, 0.0], [0.030625, 0.011236, 0.0])
+    }
+
+    pub fn ior(&self, wavelength: Float) -> Float {
+        let lambda = wavelength * 0.001;
+        let lambda2 = lambda * lambda;
+        match self {
+            Dispersion::Cauchy(a, b) => { a + b / lambda2 }
+            Dispersion::Sellmeier(b, c) => {
+                let n2 = 1.0 + (0..3).map(|i| b[i] * lambda2 / (lambda2 - c[i])).sum::<Float>();
+                n2.max(1.0).sqrt()
+            }
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Dielectric. Colored glass absorbs light on its way through (Beer-Lambert): the absorption coefficients are per unit
+// of distance, and applied when the ray leaves, to the distance it travelled inside. With dispersion the index of
+// refraction comes from the ray's wavelength instead, a ray that doesn't have one yet picks it here.
 
 pub struct Dielectric {
-    pub index_of_refraction: Float
+    pub index_of_refraction: Float,
+    pub absorption: Vec3<Float>,
+    pub dispersion: Option<Dispersion>
 }
 
 impl Dielectric {
+    pub fn new(index_of_refraction: Float) -> Self {
+        Self {
+            index_of_refraction,
+            absorption: Vec3::default(),
+            dispersion: Option::None
+        }
+    }
+
+    // Absorption that leaves the given color of white light after it went the given distance through the glass
+    pub fn set_color_at_distance(&mut self, color: &Vec3<Float>, distance: Float) {
+        let coefficient = |c: Float| { -Float::max(c, 1e-6).min(1.0).ln() / distance };
+        self.absorption = Vec3::new(coefficient(color[0]), coefficient(color[1]), coefficient(color[2]));
+    }
+
     fn reflectance(cosine: Float, ref_idx: Float) -> Float {
         let mut r0 = (1.0-ref_idx) / (1.0+ref_idx);
         r0 = r0*r0;
@@ -98,14 +344,39 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, r_in : &Ray<Float>, hit: &HitRecord) -> Option<ScatterResult> { 
-        let refraction_ratio = if hit.front_facing { 1.0/self.index_of_refraction } else { self.index_of_refraction };
+    fn scatter(&self, r_in : &Ray<Float>, hit: &HitRecord, sampler: &mut dyn Sampler) -> Option<ScatterResult> { 
+        let mut attenuation = Vec3::new(1.0, 1.0, 1.0);
+        let mut wavelength = r_in.wavelength;
+
+        let index_of_refraction = match &self.dispersion {
+            Some(dispersion) => {
+                let lambda = match r_in.wavelength {
+                    Some(lambda) => { lambda }
+                    _ => {
+                        let lambda = color::WAVELENGTH_MIN + (color::WAVELENGTH_MAX - color::WAVELENGTH_MIN) * sampler.get_1d();
+                        attenuation = color::wavelength_weight(lambda);
+                        wavelength = Some(lambda);
+                        lambda
+                    }
+                };
+                dispersion.ior(lambda)
+            }
+            _ => { self.index_of_refraction }
+        };
+
+        // Leaving the glass
+        if !hit.front_facing {
+            let distance = hit.t * r_in.dir.length();
+            attenuation = attenuation * Vec3::new((-self.absorption[0] * distance).exp(), (-self.absorption[1] * distance).exp(), (-self.absorption[2] * distance).exp());
+        }
+
+        let refraction_ratio = if hit.front_facing { 1.0/index_of_refraction } else { index_of_refraction };
 
         let unit_direction = r_in.dir.unit_vector();
         let cos_theta = Float::min(unit_direction.reverse_dir().dot(&hit.normal), 1.0);
         let sin_theta = (1.0 - cos_theta*cos_theta).sqrt();
 
-        let cannot_refract = (refraction_ratio * sin_theta) > 1.0 || (Dielectric::reflectance(cos_theta, refraction_ratio) > utils::random_float());
+        let cannot_refract = (refraction_ratio * sin_theta) > 1.0 || (Dielectric::reflectance(cos_theta, refraction_ratio) > sampler.get_1d());
         let direction;
         if cannot_refract {
             direction = utils::reflect(&unit_direction, &hit.normal);
@@ -114,8 +385,8 @@ impl Material for Dielectric {
         }
 
         Some(ScatterResult {
-            scattered: Ray { orig: hit.point, dir: direction, time: r_in.time },
-            attenuation: Vec3::new(1.0, 1.0, 1.0)
+            scattered: Ray { orig: hit.point, dir: direction, time: r_in.time, wavelength },
+            attenuation
         })
     }
 }
@@ -124,27 +395,607 @@ unsafe impl Sync for Dielectric {}
 unsafe impl Send for Dielectric {}
 
 // --------------------------------------------------------------------------------------------------------------------
-// Diffuse light
+// Rough dielectric: frosted glass made of GGX microfacets that each reflect or refract (Walter et al. 2007,
+// "Microfacet Models for Refraction through Rough Surfaces"). Roughness is read from the first channel of its texture.
+// Like Dielectric, radiance isn't scaled by the change of solid angle going in or out.
+
+pub struct RoughDielectric {
+    pub index_of_refraction: Float,
+    pub roughness: Arc<dyn Texture>
+}
+
+impl RoughDielectric {
+    pub fn new(index_of_refraction: Float, roughness: Arc<dyn Texture>) -> Self {
+        Self {
+            index_of_refraction,
+            roughness
+        }
+    }
+
+    // Shading frame, microfacet distribution, index of refraction across the surface seen from the ray's side and the
+    // direction the ray came from in the frame
+    fn at(&self, r_in : &Ray<Float>, hit: &HitRecord) -> (Frame, Ggx, Float, Vec3<Float>) {
+        let roughness = self.roughness.value(hit.u, hit.v, &hit.point).x().clamp(0.0, 1.0);
+        let eta = if hit.front_facing { self.index_of_refraction } else { 1.0 / self.index_of_refraction };
+        let frame = Frame::new(&hit.normal);
+        let wo = frame.to_local(&r_in.dir.unit_vector().reverse_dir());
+
+        (frame, Ggx::from_roughness(roughness), eta, wo)
+    }
+
+    // Microfacet normal that turns wo into wi, on the side of the normal; None where no microfacet can
+    fn half_vector(wo: &Vec3<Float>, wi: &Vec3<Float>, eta: Float) -> Option<Vec3<Float>> {
+        let reflection = wi.z() > 0.0;
+        let wh = if reflection { *wo + *wi } else { *wo + *wi * eta };
+        if utils::near_zero(&wh) {
+            return Option::None;
+        }
+
+        let wh = wh.unit_vector();
+        let wh = if wh.z() < 0.0 { wh.reverse_dir() } else { wh };
+
+        // Both directions have to be on the right side of the microfacet
+        let cos_o = wo.dot(&wh);
+        let cos_i = wi.dot(&wh);
+        if cos_o <= 0.0 || (reflection && cos_i <= 0.0) || (!reflection && cos_i >= 0.0) {
+            return Option::None;
+        }
+
+        Some(wh)
+    }
+
+    // Bsdf times cosine and pdf of scatter() picking wi
+    fn eval_and_pdf(distribution: &Ggx, eta: Float, wo: &Vec3<Float>, wi: &Vec3<Float>) -> (Float, Float) {
+        if wo.z() <= 0.0 || wi.z() == 0.0 {
+            return (0.0, 0.0);
+        }
+        let wh = match RoughDielectric::half_vector(wo, wi, eta) {
+            Some(wh) => { wh }
+            _ => { return (0.0, 0.0); }
+        };
+
+        let fresnel = microfacet::fresnel_dielectric(wo.dot(&wh), eta);
+        let d = distribution.d(&wh);
+        let g = distribution.g(wo, wi);
+        let pdf_wh = distribution.pdf(wo, &wh);
+
+        if wi.z() > 0.0 {
+            let jacobian = 1.0 / (4.0 * wo.dot(&wh));
+            (fresnel * d * g / (4.0 * wo.z()), fresnel * pdf_wh * jacobian)
+        } else {
+            let denominator = wo.dot(&wh) + eta * wi.dot(&wh);
+            let jacobian = eta * eta * wi.dot(&wh).abs() / (denominator * denominator);
+            ((1.0 - fresnel) * d * g * wo.dot(&wh) * jacobian / wo.z(), (1.0 - fresnel) * pdf_wh * jacobian)
+        }
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, r_in : &Ray<Float>, hit: &HitRecord, sampler: &mut dyn Sampler) -> Option<ScatterResult> {
+        let (frame, distribution, eta, wo) = self.at(r_in, hit);
+        if wo.z() <= 0.0 {
+            return Option::None;
+        }
+
+        // Reflect off or refract through a visible microfacet, by its Fresnel reflectance
+        let u_reflect = sampler.get_1d();
+        let wh = distribution.sample_wh(&wo, sampler.get_2d());
+        let cos_o = wo.dot(&wh);
+        let fresnel = microfacet::fresnel_dielectric(cos_o, eta);
+
+        let reflect = u_reflect < fresnel;
+        let wi = if reflect {
+            microfacet::reflect(&wo, &wh)
+        } else {
+            let cos_t = (1.0 - (1.0 - cos_o * cos_o) / (eta * eta)).max(0.0).sqrt();
+            wo.reverse_dir() * (1.0 / eta) + wh * (cos_o / eta - cos_t)
+        };
+
+        // Directions that end up on the wrong side of the surface are blocked by other microfacets
+        if reflect != (wi.z() > 0.0) {
+            return Option::None;
+        }
+
+        let (f, pdf) = RoughDielectric::eval_and_pdf(&distribution, eta, &wo, &wi);
+        if pdf <= 0.0 {
+            return Option::None;
+        }
+
+        Some(ScatterResult {
+            scattered: Ray { orig: hit.point, dir: frame.to_world(&wi), time: r_in.time, wavelength: r_in.wavelength },
+            attenuation: Vec3::new(1.0, 1.0, 1.0) * (f / pdf)
+        })
+    }
+
+    fn eval(&self, r_in : &Ray<Float>, hit: &HitRecord, direction: &Vec3<Float>) -> Vec3<Float> {
+        let (frame, distribution, eta, wo) = self.at(r_in, hit);
+        let (f, _) = RoughDielectric::eval_and_pdf(&distribution, eta, &wo, &frame.to_local(&direction.unit_vector()));
+        Vec3::new(f, f, f)
+    }
+
+    fn scattering_pdf(&self, r_in : &Ray<Float>, hit: &HitRecord, direction: &Vec3<Float>) -> Float {
+        let (frame, distribution, eta, wo) = self.at(r_in, hit);
+        RoughDielectric::eval_and_pdf(&distribution, eta, &wo, &frame.to_local(&direction.unit_vector())).1
+    }
+
+    fn has_non_specular_lobe(&self) -> bool { true }
+}
+
+unsafe impl Sync for RoughDielectric {}
+unsafe impl Send for RoughDielectric {}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Principled material, after Disney's "Physically Based Shading at Disney" (Burley 2012) and its 2015 extension with
+// transmission. Every parameter comes from a texture so it can vary over the surface, the scalar ones are read from
+// the first channel (texture::SolidColor::new_scalar for a constant). It is made of:
+//  - a diffuse lobe that gets brighter at grazing angles on rough surfaces, plus sheen for cloth-like edges
+//  - GGX specular reflection, white for dielectrics (optionally tinted towards the base color) and the base color
+//    itself for metals
+//  - a white clearcoat layer on top, with its own glossiness
+//  - smooth refraction for the transmissive part, tinted by the base color
+// Specular is the reflectance of opaque dielectrics (0.5 is 4%, like ior 1.5), ior is used by transmission.
+
+pub struct Principled {
+    pub base_color: Arc<dyn Texture>,
+    pub metallic: Arc<dyn Texture>,
+    pub roughness: Arc<dyn Texture>,
+    pub specular: Arc<dyn Texture>,
+    pub specular_tint: Arc<dyn Texture>,
+    pub sheen: Arc<dyn Texture>,
+    pub sheen_tint: Arc<dyn Texture>,
+    pub clearcoat: Arc<dyn Texture>,
+    pub clearcoat_gloss: Arc<dyn Texture>,
+    pub transmission: Arc<dyn Texture>,
+    pub ior: Arc<dyn Texture>
+}
+
+impl Principled {
+    pub fn new(base_color: Arc<dyn Texture>) -> Self {
+        Self {
+            base_color,
+            metallic: Arc::new(SolidColor::new_scalar(0.0)),
+            roughness: Arc::new(SolidColor::new_scalar(0.5)),
+            specular: Arc::new(SolidColor::new_scalar(0.5)),
+            specular_tint: Arc::new(SolidColor::new_scalar(0.0)),
+            sheen: Arc::new(SolidColor::new_scalar(0.0)),
+            sheen_tint: Arc::new(SolidColor::new_scalar(0.5)),
+            clearcoat: Arc::new(SolidColor::new_scalar(0.0)),
+            clearcoat_gloss: Arc::new(SolidColor::new_scalar(1.0)),
+            transmission: Arc::new(SolidColor::new_scalar(0.0)),
+            ior: Arc::new(SolidColor::new_scalar(1.5))
+        }
+    }
+
+    pub fn new_from_color(color: &Vec3<Float>) -> Self {
+        Self::new(Arc::new(SolidColor::new(color)))
+    }
+
+    fn at(&self, hit: &HitRecord) -> PrincipledAt {
+        let scalar = |texture: &Arc<dyn Texture>, min: Float, max: Float| { texture.value(hit.u, hit.v, &hit.point).x().clamp(min, max) };
+
+        let base_color = self.base_color.value(hit.u, hit.v, &hit.point);
+        let metallic = scalar(&self.metallic, 0.0, 1.0);
+        let roughness = scalar(&self.roughness, 0.0, 1.0);
+        let transmission = scalar(&self.transmission, 0.0, 1.0);
+        let clearcoat = scalar(&self.clearcoat, 0.0, 1.0);
+        let ior = scalar(&self.ior, 1.0, Float::MAX);
+
+        // Hue of the base color without its brightness, for the tints
+        let luminance = light::luminance(&base_color);
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        let tint = if luminance > 0.0 { base_color * (1.0 / luminance) } else { white };
+        let mix = |a: Vec3<Float>, b: Vec3<Float>, t: Float| { a * (1.0 - t) + b * t };
+
+        let diffuse_weight = (1.0 - metallic) * (1.0 - transmission);
+        let transmission_weight = (1.0 - metallic) * transmission;
+
+        // Chance of sampling the diffuse, specular, clearcoat and transmission lobe
+        let weights = [diffuse_weight, 1.0, 0.25 * clearcoat, transmission_weight];
+        let total: Float = weights.iter().sum();
+
+        PrincipledAt {
+            frame: Frame::new(&hit.normal),
+            base_color,
+            metallic,
+            roughness,
+            specular_color: mix(white, tint, scalar(&self.specular_tint, 0.0, 1.0)) * (0.08 * scalar(&self.specular, 0.0, 1.0)),
+            sheen_color: mix(white, tint, scalar(&self.sheen_tint, 0.0, 1.0)) * scalar(&self.sheen, 0.0, 1.0),
+            clearcoat,
+            clearcoat_alpha: 0.1 + (0.001 - 0.1) * scalar(&self.clearcoat_gloss, 0.0, 1.0),
+            eta: if hit.front_facing { ior } else { 1.0 / ior },
+            distribution: Ggx::from_roughness(roughness),
+            diffuse_weight,
+            transmission_weight,
+            probabilities: [weights[0] / total, weights[1] / total, weights[2] / total, weights[3] / total]
+        }
+    }
+}
+
+// The parameters at a hit point. eta is the index of refraction across the surface, seen from the side the ray is on.
+struct PrincipledAt {
+    frame: Frame,
+    base_color: Vec3<Float>,
+    metallic: Float,
+    roughness: Float,
+    specular_color: Vec3<Float>,
+    sheen_color: Vec3<Float>,
+    clearcoat: Float,
+    clearcoat_alpha: Float,
+    eta: Float,
+    distribution: Ggx,
+    diffuse_weight: Float,
+    transmission_weight: Float,
+    probabilities: [Float; 4]
+}
+
+impl PrincipledAt {
+    // Bsdf times cosine of the reflection lobes, directions in the shading frame
+    fn eval(&self, wo: &Vec3<Float>, wi: &Vec3<Float>) -> Vec3<Float> {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Vec3::default();
+        }
+
+        let pi = std::f32::consts::PI as Float;
+        let wh = (*wo + *wi).unit_vector();
+        let cos_d = wi.dot(&wh);
+        let schlick_weight = |cos: Float| { (1.0 - cos).clamp(0.0, 1.0).powi(5) };
+
+        // Diffuse and sheen
+        let mut f = Vec3::default();
+        if self.diffuse_weight > 0.0 {
+            let fd90 = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+            let retro = (1.0 + (fd90 - 1.0) * schlick_weight(wi.z())) * (1.0 + (fd90 - 1.0) * schlick_weight(wo.z()));
+            f = (self.base_color * (retro / pi) + self.sheen_color * schlick_weight(cos_d)) * self.diffuse_weight;
+        }
+
+        // Specular: dielectric reflectance for the opaque and the transmissive part, base color for metals
+        let fresnel = microfacet::fresnel_schlick(&self.specular_color, cos_d) * self.diffuse_weight
+            + Vec3::new(1.0, 1.0, 1.0) * (microfacet::fresnel_dielectric(cos_d, self.eta) * self.transmission_weight)
+            + microfacet::fresnel_schlick(&self.base_color, cos_d) * self.metallic;
+        f = f + fresnel * (self.distribution.d(&wh) * self.distribution.g(wo, wi) / (4.0 * wo.z() * wi.z()));
+
+        // Clearcoat, GTR1 distributed normals and a fixed 4% reflectance
+        if self.clearcoat > 0.0 {
+            let clearcoat_masking = Ggx::new(0.25);
+            let fr = 0.04 + 0.96 * schlick_weight(cos_d);
+            let gr = clearcoat_masking.g1(wo) * clearcoat_masking.g1(wi);
+            let fc = 0.25 * self.clearcoat * gtr1(wh.z(), self.clearcoat_alpha) * fr * gr / (4.0 * wo.z() * wi.z());
+            f = f + Vec3::new(fc, fc, fc);
+        }
+
+        f * wi.z()
+    }
+
+    fn pdf(&self, wo: &Vec3<Float>, wi: &Vec3<Float>) -> Float {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let wh = (*wo + *wi).unit_vector();
+        let jacobian = 1.0 / (4.0 * wo.dot(&wh));
+
+        self.probabilities[0] * wi.z() / (std::f32::consts::PI as Float)
+            + self.probabilities[1] * self.distribution.pdf(wo, &wh) * jacobian
+            + self.probabilities[2] * gtr1(wh.z(), self.clearcoat_alpha) * wh.z() * jacobian
+    }
+}
+
+// Generalized Trowbridge-Reitz distribution with exponent 1, used by the clearcoat
+fn gtr1(cos_theta: Float, alpha: Float) -> Float {
+    let pi = std::f32::consts::PI as Float;
+    if alpha >= 1.0 {
+        return 1.0 / pi;
+    }
+
+    let a2 = alpha * alpha;
+    (a2 - 1.0) / (pi * a2.ln() * (1.0 + (a2 - 1.0) * cos_theta * cos_theta))
+}
+
+fn sample_gtr1(alpha: Float, u: (Float, Float)) -> Vec3<Float> {
+    let a2 = alpha * alpha;
+    let cos_theta = ((1.0 - a2.powf(1.0 - u.0)) / (1.0 - a2)).clamp(0.0, 1.0).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * (std::f32::consts::PI as Float) * u.1;
+
+    Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+impl Material for Principled {
+    fn scatter(&self, r_in : &Ray<Float>, hit: &HitRecord, sampler: &mut dyn Sampler) -> Option<ScatterResult> {
+        let at = self.at(hit);
+        let unit_direction = r_in.dir.unit_vector();
+        let wo = at.frame.to_local(&unit_direction.reverse_dir());
+
+        let u_lobe = sampler.get_1d();
+        let u = sampler.get_2d();
+
+        // Refraction is a specular event; the light it doesn't let through is in the specular lobe
+        let p_transmission = at.probabilities[3];
+        if u_lobe < p_transmission {
+            let fresnel = microfacet::fresnel_dielectric(wo.z(), at.eta);
+            if fresnel >= 1.0 {
+                return Option::None;
+            }
+
+            return Some(ScatterResult {
+                scattered: Ray { orig: hit.point, dir: utils::refract(&unit_direction, &hit.normal, 1.0 / at.eta), time: r_in.time, wavelength: r_in.wavelength },
+                attenuation: at.base_color * (at.transmission_weight * (1.0 - fresnel) / p_transmission)
+            });
+        }
+
+        let u_lobe = u_lobe - p_transmission;
+        let wi = if u_lobe < at.probabilities[0] {
+            utils::cosine_hemisphere_from(u)
+        } else if u_lobe < at.probabilities[0] + at.probabilities[1] {
+            microfacet::reflect(&wo, &at.distribution.sample_wh(&wo, u))
+        } else {
+            microfacet::reflect(&wo, &sample_gtr1(at.clearcoat_alpha, u))
+        };
+
+        let pdf = at.pdf(&wo, &wi);
+        if pdf <= 0.0 {
+            return Option::None;
+        }
+
+        Some(ScatterResult {
+            scattered: Ray { orig: hit.point, dir: at.frame.to_world(&wi), time: r_in.time, wavelength: r_in.wavelength },
+            attenuation: at.eval(&wo, &wi) * (1.0 / pdf)
+        })
+    }
+
+    fn eval(&self, r_in : &Ray<Float>, hit: &HitRecord, direction: &Vec3<Float>) -> Vec3<Float> {
+        let at = self.at(hit);
+        at.eval(&at.frame.to_local(&r_in.dir.unit_vector().reverse_dir()), &at.frame.to_local(&direction.unit_vector()))
+    }
+
+    fn scattering_pdf(&self, r_in : &Ray<Float>, hit: &HitRecord, direction: &Vec3<Float>) -> Float {
+        let at = self.at(hit);
+        at.pdf(&at.frame.to_local(&r_in.dir.unit_vector().reverse_dir()), &at.frame.to_local(&direction.unit_vector()))
+    }
+
+    fn has_non_specular_lobe(&self) -> bool { true }
+}
+
+unsafe impl Sync for Principled {}
+unsafe impl Send for Principled {}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Coated: a clear (or tinted) dielectric coat over any other material, like car paint, varnished wood or glossy
+// plastic. The coat reflects by its Fresnel reflectance off GGX microfacets (smooth by default), what it lets through
+// reaches the base, and what comes back out of the base loses the coat's Fresnel reflectance once more.
+// Light going straight through a coat of thickness 1 gets the tint color; at an angle it travels further inside, and
+// thicker coats tint more. Refraction is only used for those distances, the base is seen with unbent directions.
+// Light sampling sees the base through eval and scattering_pdf. Over a specular base (Metal, Dielectric) the coated
+// surface is specular as well, lit by scattered rays only, so point, spot and directional lights leave no highlight on
+// the coat; Conductor or Principled work better under a coat.
+
+pub struct Coated {
+    pub base: Arc<dyn Material>,
+    pub index_of_refraction: Float,
+    pub roughness: Arc<dyn Texture>,
+    pub thickness: Float,
+    pub tint: Vec3<Float>
+}
+
+impl Coated {
+    pub fn new(base: Arc<dyn Material>) -> Self {
+        Self {
+            base,
+            index_of_refraction: 1.5,
+            roughness: Arc::new(SolidColor::new_scalar(0.0)),
+            thickness: 1.0,
+            tint: Vec3::new(1.0, 1.0, 1.0)
+        }
+    }
+
+    // Shading frame, coat microfacets, the direction the ray came from in that frame and the chance of sampling the coat
+    fn at(&self, r_in : &Ray<Float>, hit: &HitRecord) -> (Frame, Ggx, Vec3<Float>, Float) {
+        let roughness = self.roughness.value(hit.u, hit.v, &hit.point).x().clamp(0.0, 1.0);
+        let frame = Frame::new(&hit.normal);
+        let wo = frame.to_local(&r_in.dir.unit_vector().reverse_dir());
+        let p_coat = Float::max(microfacet::fresnel_dielectric(wo.z(), self.index_of_refraction), 0.25);
+
+        (frame, Ggx::from_roughness(roughness), wo, p_coat)
+    }
+
+    // How much of the light the base sends from wi to wo gets through the coat, in and out
+    fn transmittance(&self, wo: &Vec3<Float>, wi: &Vec3<Float>) -> Vec3<Float> {
+        let eta = self.index_of_refraction;
+        let fresnel = |w: &Vec3<Float>| { microfacet::fresnel_dielectric(w.z().abs(), eta) };
+        let cos_inside = |w: &Vec3<Float>| { Float::max(1.0 - (1.0 - w.z() * w.z()) / (eta * eta), 0.0).sqrt() };
+
+        let distance = self.thickness * (1.0 / cos_inside(wo) + 1.0 / cos_inside(wi));
+        let tint = Vec3::new(self.tint[0].max(0.0).powf(distance), self.tint[1].max(0.0).powf(distance), self.tint[2].max(0.0).powf(distance));
+
+        tint * ((1.0 - fresnel(wo)) * (1.0 - fresnel(wi)))
+    }
+
+    // Bsdf times cosine of the coat's reflection
+    fn coat_eval(&self, distribution: &Ggx, wo: &Vec3<Float>, wi: &Vec3<Float>) -> Float {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let wh = (*wo + *wi).unit_vector();
+        microfacet::fresnel_dielectric(wo.dot(&wh), self.index_of_refraction) * distribution.d(&wh) * distribution.g(wo, wi) / (4.0 * wo.z())
+    }
+
+    fn coat_pdf(distribution: &Ggx, wo: &Vec3<Float>, wi: &Vec3<Float>) -> Float {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let wh = (*wo + *wi).unit_vector();
+        distribution.pdf(wo, &wh) / (4.0 * wo.dot(&wh))
+    }
+}
+
+impl Material for Coated {
+    // Either off the coat or through it to the base, each weighted by the chance of picking it
+    fn scatter(&self, r_in : &Ray<Float>, hit: &HitRecord, sampler: &mut dyn Sampler) -> Option<ScatterResult> {
+        // From inside an object, there's no coat
+        if !hit.front_facing {
+            return self.base.scatter(r_in, hit, sampler);
+        }
+
+        let (frame, distribution, wo, p_coat) = self.at(r_in, hit);
+        if sampler.get_1d() < p_coat {
+            let wh = distribution.sample_wh(&wo, sampler.get_2d());
+            let wi = microfacet::reflect(&wo, &wh);
+            if wo.z() <= 0.0 || wi.z() <= 0.0 {
+                return Option::None;
+            }
+
+            let fresnel = microfacet::fresnel_dielectric(wo.dot(&wh), self.index_of_refraction);
+            let weight = fresnel * distribution.g(&wo, &wi) / (distribution.g1(&wo) * p_coat);
+            return Some(ScatterResult {
+                scattered: Ray { orig: hit.point, dir: frame.to_world(&wi), time: r_in.time, wavelength: r_in.wavelength },
+                attenuation: Vec3::new(weight, weight, weight)
+            });
+        }
+
+        let base = self.base.scatter(r_in, hit, sampler)?;
+        let wi = frame.to_local(&base.scattered.dir.unit_vector());
+
+        Some(ScatterResult {
+            scattered: base.scattered,
+            attenuation: base.attenuation * self.transmittance(&wo, &wi) * (1.0 / (1.0 - p_coat))
+        })
+    }
+
+    fn eval(&self, r_in : &Ray<Float>, hit: &HitRecord, direction: &Vec3<Float>) -> Vec3<Float> {
+        if !hit.front_facing {
+            return self.base.eval(r_in, hit, direction);
+        }
+
+        let (frame, distribution, wo, _) = self.at(r_in, hit);
+        let wi = frame.to_local(&direction.unit_vector());
+        let coat = self.coat_eval(&distribution, &wo, &wi);
+
+        self.base.eval(r_in, hit, direction) * self.transmittance(&wo, &wi) + Vec3::new(coat, coat, coat)
+    }
+
+    fn scattering_pdf(&self, r_in : &Ray<Float>, hit: &HitRecord, direction: &Vec3<Float>) -> Float {
+        if !hit.front_facing {
+            return self.base.scattering_pdf(r_in, hit, direction);
+        }
+
+        // A specular base (Metal, Dielectric) may have picked the direction itself, which light sampling can never
+        // find, so the whole surface counts as specular
+        let base_pdf = self.base.scattering_pdf(r_in, hit, direction);
+        if base_pdf <= 0.0 {
+            return 0.0;
+        }
+
+        let (frame, distribution, wo, p_coat) = self.at(r_in, hit);
+        let wi = frame.to_local(&direction.unit_vector());
+
+        p_coat * Coated::coat_pdf(&distribution, &wo, &wi) + (1.0 - p_coat) * base_pdf
+    }
+
+    fn has_non_specular_lobe(&self) -> bool { self.base.has_non_specular_lobe() }
+}
+
+unsafe impl Sync for Coated {}
+unsafe impl Send for Coated {}
+
+// --------------------------------------------------------------------------------------------------------------------
+// How strong an emitter is. Scenes are taken to be in meters, and one unit of radiance in the renderer to be
+// 1 W/(sr m^2). The surface emits like a perfectly diffuse (lambertian) one, so radiance = power / (pi * area),
+// where area counts every face the power leaves through.
+
+#[derive(Copy, Clone, Debug)]
+pub enum LightPower {
+    // Radiance straight away, W/(sr m^2)
+    Radiance(Float),
+    // Total radiant power
+    Watts(Float),
+    // Total luminous power, at the peak efficacy of 683 lm/W
+    Lumens(Float)
+}
+
+impl LightPower {
+    pub fn radiance(&self, area: Float) -> Float {
+        let pi = std::f32::consts::PI as Float;
+        match *self {
+            LightPower::Radiance(radiance) => { radiance }
+            LightPower::Watts(watts) => { watts / (pi * area) }
+            LightPower::Lumens(lumens) => { lumens / (683.0 * pi * area) }
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Diffuse light. Emits from both sides by default; front is the side the shape's normal points to (outside of a
+// sphere, +y for an XZRect). Lights can be hidden from camera rays, and from rays scattered off diffuse or specular
+// surfaces, those rays then go through them.
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum EmitSides {
+    Front,
+    Back,
+    Both
+}
 
 pub struct DiffuseLight {
-    pub emit: Arc<dyn Texture>
+    pub emit: Arc<dyn Texture>,
+    pub sides: EmitSides,
+    pub visible_to_camera: bool,
+    pub affects_diffuse: bool,
+    pub affects_specular: bool
 }
 
 impl DiffuseLight {
     pub fn new(emit_texture: Arc<dyn Texture>) -> Self {
         Self {
-            emit: emit_texture.clone()
+            emit: emit_texture.clone(),
+            sides: EmitSides::Both,
+            visible_to_camera: true,
+            affects_diffuse: true,
+            affects_specular: true
         }
     }
+
+    // Light of the given color (normalized to a luminance of 1 first, e.g. color::blackbody) and strength, for a
+    // shape with the given surface area (Sphere::area, XZRect::area, ...) emitting from the given sides. Both sides
+    // share the power between the two faces; a sphere lights the scene from its front (outside).
+    pub fn new_with_power(color: &Vec3<Float>, power: LightPower, area: Float, sides: EmitSides) -> Self {
+        let luminance = light::luminance(color);
+        let color = if luminance > 0.0 { *color * (1.0 / luminance) } else { *color };
+        let emitting_area = if sides == EmitSides::Both { 2.0 * area } else { area };
+
+        let mut diffuse_light = Self::new(Arc::new(SolidColor::new(&(color * power.radiance(emitting_area)))));
+        diffuse_light.sides = sides;
+        diffuse_light
+    }
+
+    // Same for a black body at the given temperature (Kelvin)
+    pub fn new_with_temperature(kelvin: Float, power: LightPower, area: Float, sides: EmitSides) -> Self {
+        Self::new_with_power(&color::blackbody(kelvin), power, area, sides)
+    }
 }
 
 impl Material for DiffuseLight {
-    fn scatter(&self, _r_in : &Ray<Float>, _hit: &HitRecord) -> Option<ScatterResult> { 
+    fn scatter(&self, _r_in : &Ray<Float>, _hit: &HitRecord, _sampler: &mut dyn Sampler) -> Option<ScatterResult> { 
         Option::None
     }
 
-    fn emitted(&self, u: Float, v: Float, p: &Vec3<Float>) -> Vec3<Float> {
-        self.emit.value(u, v, &p)
+    fn emitted(&self, hit: &HitRecord) -> Vec3<Float> {
+        let emits = match self.sides {
+            EmitSides::Front => { hit.front_facing }
+            EmitSides::Back => { !hit.front_facing }
+            EmitSides::Both => { true }
+        };
+
+        if emits { self.emit.value(hit.u, hit.v, &hit.point) } else { Vec3::default() }
+    }
+
+    fn visible(&self, kind: RayKind) -> bool {
+        match kind {
+            RayKind::Camera => { self.visible_to_camera }
+            RayKind::Diffuse => { self.affects_diffuse }
+            RayKind::Specular => { self.affects_specular }
+        }
     }
 }
 
@@ -173,12 +1024,23 @@ impl Isotropic {
 }
 
 impl Material for Isotropic {
-    fn scatter(&self, r_in : &Ray<Float>, hit: &HitRecord) -> Option<ScatterResult> { 
+    fn scatter(&self, r_in : &Ray<Float>, hit: &HitRecord, sampler: &mut dyn Sampler) -> Option<ScatterResult> { 
         Some(ScatterResult {
-            scattered: Ray { orig: hit.point, dir: random_in_unit_sphere(), time: r_in.time },
+            scattered: Ray { orig: hit.point, dir: utils::unit_vec3_from(sampler.get_2d()), time: r_in.time, wavelength: r_in.wavelength },
             attenuation: self.albedo.value(hit.u, hit.v, &hit.point)
         })
     }
+
+    fn eval(&self, r_in : &Ray<Float>, hit: &HitRecord, direction: &Vec3<Float>) -> Vec3<Float> {
+        self.albedo.value(hit.u, hit.v, &hit.point) * self.scattering_pdf(r_in, hit, direction)
+    }
+
+    // Uniform over the sphere
+    fn scattering_pdf(&self, _r_in : &Ray<Float>, _hit: &HitRecord, _direction: &Vec3<Float>) -> Float {
+        1.0 / (4.0 * (std::f32::consts::PI as Float))
+    }
+
+    fn has_non_specular_lobe(&self) -> bool { true }
 }
 
 unsafe impl Sync for Isotropic {}
@@ -0,0 +1,205 @@
+use crate::vec3::Vec3;
+use crate::types::*;
+use crate::utils;
+
+// --------------------------------------------------------------------------------------------------------------------
+// Shading frame: directions in it have the surface normal along +z, so cos(theta) is just z
+
+pub struct Frame {
+    pub tangent: Vec3<Float>,
+    pub bitangent: Vec3<Float>,
+    pub normal: Vec3<Float>
+}
+
+impl Frame {
+    pub fn new(normal: &Vec3<Float>) -> Self {
+        let (tangent, bitangent) = utils::orthonormal_basis(normal);
+        Self {
+            tangent,
+            bitangent,
+            normal: *normal
+        }
+    }
+
+    // Frame with the tangent along the part of the given direction that lies in the surface, for anisotropic
+    // materials. Falls back to an arbitrary one where the direction is (nearly) along the normal.
+    pub fn new_with_tangent(normal: &Vec3<Float>, direction: &Vec3<Float>) -> Self {
+        let in_plane = *direction - *normal * direction.dot(normal);
+        if in_plane.length_squared() < 1e-8 {
+            return Self::new(normal);
+        }
+
+        let tangent = in_plane.unit_vector();
+        Self {
+            tangent,
+            bitangent: normal.cross(&tangent),
+            normal: *normal
+        }
+    }
+
+    pub fn to_local(&self, v: &Vec3<Float>) -> Vec3<Float> {
+        Vec3::new(v.dot(&self.tangent), v.dot(&self.bitangent), v.dot(&self.normal))
+    }
+
+    pub fn to_world(&self, v: &Vec3<Float>) -> Vec3<Float> {
+        self.tangent * v.x() + self.bitangent * v.y() + self.normal * v.z()
+    }
+}
+
+// Direction wo mirrored about the (unit) normal n, both pointing away from the surface
+pub fn reflect(wo: &Vec3<Float>, n: &Vec3<Float>) -> Vec3<Float> {
+    *n * (2.0 * wo.dot(n)) - *wo
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Fresnel reflectance
+
+// Schlick's approximation, for a reflectance of f0 at normal incidence
+pub fn fresnel_schlick(f0: &Vec3<Float>, cos_theta: Float) -> Vec3<Float> {
+    let m = (1.0 - cos_theta).clamp(0.0, 1.0).powi(5);
+    *f0 + (Vec3::new(1.0, 1.0, 1.0) - *f0) * m
+}
+
+// Unpolarized reflectance of a dielectric boundary, eta is the index of refraction on the far side over the one on the
+// side the light comes from. 1 under total internal reflection.
+pub fn fresnel_dielectric(cos_theta_i: Float, eta: Float) -> Float {
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+    let sin2_theta_t = (1.0 - cos_theta_i * cos_theta_i) / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return 1.0;
+    }
+
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+
+    0.5 * (r_parallel * r_parallel + r_perpendicular * r_perpendicular)
+}
+
+// Unpolarized reflectance of a conductor with complex index of refraction eta + ik (relative to the outside medium),
+// per color channel (pbrt's FrConductor)
+pub fn fresnel_conductor(cos_theta_i: Float, eta: &Vec3<Float>, k: &Vec3<Float>) -> Vec3<Float> {
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+    let cos2_theta = cos_theta_i * cos_theta_i;
+    let sin2_theta = 1.0 - cos2_theta;
+
+    let channel = |eta: Float, k: Float| {
+        let t0 = eta * eta - k * k - sin2_theta;
+        let a2_plus_b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+        let t1 = a2_plus_b2 + cos2_theta;
+        let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+        let t2 = 2.0 * cos_theta_i * a;
+        let r_s = (t1 - t2) / (t1 + t2);
+
+        let t3 = cos2_theta * a2_plus_b2 + sin2_theta * sin2_theta;
+        let t4 = t2 * sin2_theta;
+        let r_p = r_s * (t3 - t4) / (t3 + t4);
+
+        0.5 * (r_p + r_s)
+    };
+
+    Vec3::new(channel(eta.x(), k.x()), channel(eta.y(), k.y()), channel(eta.z(), k.z()))
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// GGX (Trowbridge-Reitz) microfacet distribution with Smith masking. All directions are in the shading frame and
+// point away from the surface. Anisotropic surfaces are rougher along the tangent (alpha_x) than across it (alpha_y).
+
+#[derive(Copy, Clone, Debug)]
+pub struct Ggx {
+    pub alpha_x: Float,
+    pub alpha_y: Float
+}
+
+impl Ggx {
+    pub fn new(alpha: Float) -> Self {
+        Self::new_anisotropic(alpha, alpha)
+    }
+
+    pub fn new_anisotropic(alpha_x: Float, alpha_y: Float) -> Self {
+        Self {
+            alpha_x,
+            alpha_y
+        }
+    }
+
+    // Artist friendly roughness in [0, 1] is squared; a tiny alpha is kept so perfectly smooth still works
+    pub fn from_roughness(roughness: Float) -> Self {
+        Self::from_roughness_anisotropic(roughness, 0.0)
+    }
+
+    // Anisotropy in [0, 1) stretches the highlights along the tangent (Burley 2012)
+    pub fn from_roughness_anisotropic(roughness: Float, anisotropy: Float) -> Self {
+        let alpha = roughness * roughness;
+        let aspect = (1.0 - 0.9 * anisotropy.clamp(0.0, 1.0)).sqrt();
+
+        Self::new_anisotropic(Float::max(alpha / aspect, 0.001), Float::max(alpha * aspect, 0.001))
+    }
+
+    // Density of microfacet normals
+    pub fn d(&self, wh: &Vec3<Float>) -> Float {
+        if wh.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let x = wh.x() / self.alpha_x;
+        let y = wh.y() / self.alpha_y;
+        let length2 = x * x + y * y + wh.z() * wh.z();
+        1.0 / ((std::f32::consts::PI as Float) * self.alpha_x * self.alpha_y * length2 * length2)
+    }
+
+    fn lambda(&self, w: &Vec3<Float>) -> Float {
+        let cos2_theta = w.z() * w.z();
+        if cos2_theta <= 0.0 {
+            return 0.0;
+        }
+
+        // alpha in the direction of w, times tan(theta)
+        let alpha2_tan2_theta = (self.alpha_x * self.alpha_x * w.x() * w.x() + self.alpha_y * self.alpha_y * w.y() * w.y()) / cos2_theta;
+        0.5 * (-1.0 + (1.0 + alpha2_tan2_theta).sqrt())
+    }
+
+    // Fraction of microfacets seen from w
+    pub fn g1(&self, w: &Vec3<Float>) -> Float {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    // Fraction seen from both directions (height correlated)
+    pub fn g(&self, wo: &Vec3<Float>, wi: &Vec3<Float>) -> Float {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // Microfacet normal visible from wo (Heitz 2018, "Sampling the GGX Distribution of Visible Normals")
+    pub fn sample_wh(&self, wo: &Vec3<Float>, u: (Float, Float)) -> Vec3<Float> {
+        let flip = wo.z() < 0.0;
+        let wo = if flip { wo.reverse_dir() } else { *wo };
+
+        // Stretch the view direction so the distribution becomes a hemisphere
+        let vh = Vec3::new(self.alpha_x * wo.x(), self.alpha_y * wo.y(), wo.z()).unit_vector();
+        let length2 = vh.x() * vh.x() + vh.y() * vh.y();
+        let t1 = if length2 > 0.0 { Vec3::new(-vh.y(), vh.x(), 0.0) * (1.0 / length2.sqrt()) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let t2 = vh.cross(&t1);
+
+        // Point on the projected disk, squeezed towards the visible half
+        let r = u.0.sqrt();
+        let phi = 2.0 * (std::f32::consts::PI as Float) * u.1;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z());
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * phi.sin();
+        let nh = t1 * p1 + t2 * p2 + vh * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+
+        // And back to the ellipsoid
+        let wh = Vec3::new(self.alpha_x * nh.x(), self.alpha_y * nh.y(), nh.z().max(1e-6)).unit_vector();
+        if flip { wh.reverse_dir() } else { wh }
+    }
+
+    // Density of sample_wh picking wh
+    pub fn pdf(&self, wo: &Vec3<Float>, wh: &Vec3<Float>) -> Float {
+        let cos_theta_o = wo.z().abs();
+        if cos_theta_o <= 0.0 {
+            return 0.0;
+        }
+
+        self.d(wh) * self.g1(wo) * wo.dot(wh).abs() / cos_theta_o
+    }
+}
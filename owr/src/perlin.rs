@@ -17,20 +17,28 @@ pub struct Perlin {
 }
 
 impl Perlin {
+    // Seeded from the deterministic stream, like the rest of the scene generation
     pub fn new() -> Self {
+        Perlin::new_with_seed(utils::det_rand_u32())
+    }
+
+    pub fn new_with_seed(seed: u32) -> Self {
+        let mut rng = utils::Rng::new(seed);
+
         let mut random_floats = Vec::with_capacity(POINT_COUNT);
         for _ in 0..POINT_COUNT {
-            random_floats.push(utils::random_float());
+            random_floats.push(rng.next_float());
         }
 
         let mut random_vec = Vec::with_capacity(POINT_COUNT);
         for _i in 0..POINT_COUNT {
-            random_vec.push(utils::random_range_vec3(-1.0, 1.0).unit_vector());
+            let v = Vec3::new(rng.next_range(-1.0, 1.0), rng.next_range(-1.0, 1.0), rng.next_range(-1.0, 1.0));
+            random_vec.push(v.unit_vector());
         }
 
-        let perm_x = Perlin::perlin_generate_perm();
-        let perm_y = Perlin::perlin_generate_perm();
-        let perm_z = Perlin::perlin_generate_perm();
+        let perm_x = Perlin::perlin_generate_perm(&mut rng);
+        let perm_y = Perlin::perlin_generate_perm(&mut rng);
+        let perm_z = Perlin::perlin_generate_perm(&mut rng);
 
         Self {
             perm_x,
@@ -162,19 +170,19 @@ impl Perlin {
         accum
     }
 
-    fn perlin_generate_perm() -> Vec<u32> {
+    fn perlin_generate_perm(rng: &mut utils::Rng) -> Vec<u32> {
         let mut permuted_values = Vec::with_capacity(POINT_COUNT);
         for i in 0..POINT_COUNT {
             permuted_values.push(i as u32);
         }
-        Perlin::permute(&mut permuted_values);
+        Perlin::permute(&mut permuted_values, rng);
 
         permuted_values
     }
 
-    fn permute(values: &mut Vec<u32>) {
+    fn permute(values: &mut [u32], rng: &mut utils::Rng) {
         for i in (1..values.len()).rev() {
-            let rand_i = utils::rand_u32() as usize % i;
+            let rand_i = rng.next_u32() as usize % i;
             values.swap(i, rand_i);
         }
     }
@@ -3,10 +3,15 @@ use std::ops::{Add, Mul};
 
 // --------------------------------------------------------------------------------------------------------------------
 
+// Wavelength (nm) is picked by the first dispersive surface on a path, and kept from there on so every later one
+// bends the ray by the same amount. Until then the ray carries all colors.
+
+#[derive(Copy, Clone)]
 pub struct Ray<T> {
     pub orig: Vec3<T>,
     pub dir: Vec3<T>,
-    pub time: T
+    pub time: T,
+    pub wavelength: Option<T>
 }
 
 impl<T> Ray<T> {
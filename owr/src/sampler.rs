@@ -0,0 +1,534 @@
+use crate::types::*;
+use crate::utils;
+
+use std::rc::Rc;
+
+// --------------------------------------------------------------------------------------------------------------------
+// Sampler trait
+//
+// A sampler hands out the random numbers for one pixel sample at a time. Every call consumes the next dimension,
+// so the camera, the materials and the integrator should ask in a consistent order for low-discrepancy samplers
+// to pay off.
+
+pub trait Sampler {
+    fn start_sample(&mut self, pixel_x: u32, pixel_y: u32, sample_index: u32);
+    fn get_1d(&mut self) -> Float;
+    fn get_2d(&mut self) -> (Float, Float);
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Sampler types
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SamplerType {
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+    BlueNoise
+}
+
+impl Default for SamplerType {
+    fn default() -> Self {
+        SamplerType::Independent
+    }
+}
+
+impl SamplerType {
+    pub fn from_name(name: &str) -> Option<SamplerType> {
+        match name {
+            "independent" | "random" => { Some(SamplerType::Independent) }
+            "stratified" => { Some(SamplerType::Stratified) }
+            "halton" => { Some(SamplerType::Halton) }
+            "sobol" => { Some(SamplerType::Sobol) }
+            "blue-noise" => { Some(SamplerType::BlueNoise) }
+            _ => { None }
+        }
+    }
+
+    // Everything a sampler returns is a function of (seed, pixel, sample index, dimension) only,
+    // so renders with the same seed match regardless of thread count or scheduling
+    pub fn create(&self, samples_per_pixel: u32, seed: u32) -> Box<dyn Sampler> {
+        match self {
+            SamplerType::Independent => { Box::new(IndependentSampler::new(seed)) }
+            SamplerType::Stratified => { Box::new(StratifiedSampler::new(samples_per_pixel, seed)) }
+            SamplerType::Halton => { Box::new(HaltonSampler::new(seed)) }
+            SamplerType::Sobol => { Box::new(SobolSampler::new(seed)) }
+            SamplerType::BlueNoise => { Box::new(BlueNoiseSampler::new(seed)) }
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Independent: plain uniform random numbers
+
+pub struct IndependentSampler {
+    seed: u32,
+    rng: utils::Rng
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            rng: utils::Rng::new(seed)
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_sample(&mut self, pixel_x: u32, pixel_y: u32, sample_index: u32) {
+        self.rng = sample_rng(self.seed, pixel_x, pixel_y, sample_index);
+    }
+
+    fn get_1d(&mut self) -> Float {
+        self.rng.next_float()
+    }
+
+    fn get_2d(&mut self) -> (Float, Float) {
+        (self.rng.next_float(), self.rng.next_float())
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Stratified: jittered samples, every dimension gets its own shuffle of the strata
+
+pub struct StratifiedSampler {
+    samples_per_pixel: u32,
+    x_strata: u32,
+    y_strata: u32,
+    seed: u32,
+    pixel_seed: u32,
+    sample_index: u32,
+    dimension: u32,
+    rng: utils::Rng
+}
+
+impl StratifiedSampler {
+    pub fn new(samples_per_pixel: u32, seed: u32) -> Self {
+        let samples_per_pixel = u32::max(samples_per_pixel, 1);
+        let x_strata = (samples_per_pixel as Float).sqrt().ceil() as u32;
+        let y_strata = (samples_per_pixel + x_strata - 1) / x_strata;
+
+        Self {
+            samples_per_pixel,
+            x_strata,
+            y_strata,
+            seed,
+            pixel_seed: 0,
+            sample_index: 0,
+            dimension: 0,
+            rng: utils::Rng::new(seed)
+        }
+    }
+
+    fn next_seed(&mut self) -> u32 {
+        self.dimension += 1;
+        utils::hash_u32(self.pixel_seed ^ utils::hash_u32(self.dimension))
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_sample(&mut self, pixel_x: u32, pixel_y: u32, sample_index: u32) {
+        self.pixel_seed = pixel_seed(self.seed, pixel_x, pixel_y);
+        self.sample_index = sample_index;
+        self.dimension = 0;
+        self.rng = sample_rng(self.seed, pixel_x, pixel_y, sample_index);
+    }
+
+    fn get_1d(&mut self) -> Float {
+        let seed = self.next_seed();
+        let stratum = permute(self.sample_index % self.samples_per_pixel, self.samples_per_pixel, seed);
+
+        ((stratum as Float) + self.rng.next_float()) / (self.samples_per_pixel as Float)
+    }
+
+    fn get_2d(&mut self) -> (Float, Float) {
+        let seed = self.next_seed();
+        let num_strata = self.x_strata * self.y_strata;
+        let stratum = permute(self.sample_index % num_strata, num_strata, seed);
+        let x = stratum % self.x_strata;
+        let y = stratum / self.x_strata;
+
+        (
+            ((x as Float) + self.rng.next_float()) / (self.x_strata as Float),
+            ((y as Float) + self.rng.next_float()) / (self.y_strata as Float)
+        )
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Halton: radical inverse in a different prime base per dimension, decorrelated
+// between pixels with a per-pixel random shift (Cranley-Patterson rotation)
+
+const PRIMES: [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53,
+    59, 61, 67, 71, 73, 79, 83, 89, 97, 101, 103, 107, 109, 113, 127, 131
+];
+
+pub struct HaltonSampler {
+    seed: u32,
+    pixel_seed: u32,
+    sample_index: u32,
+    dimension: u32,
+    rng: utils::Rng
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            pixel_seed: 0,
+            sample_index: 0,
+            dimension: 0,
+            rng: utils::Rng::new(seed)
+        }
+    }
+
+    fn radical_inverse(base: u32, index: u32) -> Float {
+        let inv_base = 1.0 / (base as f64);
+        let mut index = index;
+        let mut inv_base_n = 1.0;
+        let mut reversed = 0.0;
+        while index > 0 {
+            let next = index / base;
+            let digit = index - next * base;
+            reversed = reversed * (base as f64) + (digit as f64);
+            inv_base_n *= inv_base;
+            index = next;
+        }
+
+        Float::min((reversed * inv_base_n) as Float, ONE_MINUS_EPSILON)
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_sample(&mut self, pixel_x: u32, pixel_y: u32, sample_index: u32) {
+        self.pixel_seed = pixel_seed(self.seed, pixel_x, pixel_y);
+        self.sample_index = sample_index;
+        self.dimension = 0;
+        self.rng = sample_rng(self.seed, pixel_x, pixel_y, sample_index);
+    }
+
+    fn get_1d(&mut self) -> Float {
+        let dimension = self.dimension;
+        self.dimension += 1;
+
+        // Past the prime table the sequence gets poor anyway, so fall back to plain random numbers
+        if (dimension as usize) >= PRIMES.len() {
+            return self.rng.next_float();
+        }
+
+        let shift = uint_to_float(utils::hash_u32(self.pixel_seed ^ utils::hash_u32(dimension)));
+        let value = HaltonSampler::radical_inverse(PRIMES[dimension as usize], self.sample_index) + shift;
+        if value >= 1.0 { value - 1.0 } else { value }
+    }
+
+    fn get_2d(&mut self) -> (Float, Float) {
+        (self.get_1d(), self.get_1d())
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Sobol: the first two Sobol dimensions with hash-based Owen scrambling, padded to higher dimensions
+// by shuffling the sample index per dimension pair ("Practical Hash-based Owen Scrambling", Burley 2020)
+
+pub struct SobolSampler {
+    seed: u32,
+    pixel_seed: u32,
+    sample_index: u32,
+    dimension: u32
+}
+
+impl SobolSampler {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            pixel_seed: 0,
+            sample_index: 0,
+            dimension: 0
+        }
+    }
+
+    fn next_seed(&mut self) -> u32 {
+        self.dimension += 1;
+        utils::hash_u32(self.pixel_seed ^ utils::hash_u32(self.dimension))
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_sample(&mut self, pixel_x: u32, pixel_y: u32, sample_index: u32) {
+        self.pixel_seed = pixel_seed(self.seed, pixel_x, pixel_y);
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> Float {
+        let seed = self.next_seed();
+        let index = nested_uniform_scramble(self.sample_index, seed);
+
+        uint_to_float(nested_uniform_scramble(sobol(index, 0), utils::hash_u32(seed ^ 0x5bd1e995)))
+    }
+
+    fn get_2d(&mut self) -> (Float, Float) {
+        let seed = self.next_seed();
+        let index = nested_uniform_scramble(self.sample_index, seed);
+
+        (
+            uint_to_float(nested_uniform_scramble(sobol(index, 0), utils::hash_u32(seed ^ 0x5bd1e995))),
+            uint_to_float(nested_uniform_scramble(sobol(index, 1), utils::hash_u32(seed ^ 0x68e31da4)))
+        )
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Blue noise dithered: the same Sobol points in every pixel, each pixel shifted by a value from a blue noise
+// tile (a different part of the tile per dimension). Error ends up as high frequency noise, which reads as
+// less noisy at low sample counts.
+
+const BLUE_NOISE_SIZE: usize = 32;
+
+thread_local! {
+    static BLUE_NOISE: Rc<Vec<Float>> = Rc::new(generate_blue_noise(BLUE_NOISE_SIZE));
+}
+
+pub struct BlueNoiseSampler {
+    tile: Rc<Vec<Float>>,
+    seed: u32,
+    pixel_x: u32,
+    pixel_y: u32,
+    sample_index: u32,
+    dimension: u32
+}
+
+impl BlueNoiseSampler {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            tile: BLUE_NOISE.with(|tile| tile.clone()),
+            seed: utils::hash_u32(seed),
+            pixel_x: 0,
+            pixel_y: 0,
+            sample_index: 0,
+            dimension: 0
+        }
+    }
+
+    // Shift for the current pixel, read from the tile at an offset that depends on the dimension
+    fn shift(&self, dimension: u32) -> Float {
+        let offset = utils::hash_u32(self.seed ^ dimension);
+        let x = (self.pixel_x as usize + (offset & 0xffff) as usize) % BLUE_NOISE_SIZE;
+        let y = (self.pixel_y as usize + (offset >> 16) as usize) % BLUE_NOISE_SIZE;
+
+        self.tile[y * BLUE_NOISE_SIZE + x]
+    }
+
+    fn shifted(value: Float, shift: Float) -> Float {
+        let value = value + shift;
+        if value >= 1.0 { value - 1.0 } else { value }
+    }
+}
+
+impl Sampler for BlueNoiseSampler {
+    fn start_sample(&mut self, pixel_x: u32, pixel_y: u32, sample_index: u32) {
+        self.pixel_x = pixel_x;
+        self.pixel_y = pixel_y;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> Float {
+        self.dimension += 1;
+        let seed = utils::hash_u32(self.seed ^ utils::hash_u32(self.dimension));
+        let index = nested_uniform_scramble(self.sample_index, seed);
+        let value = uint_to_float(nested_uniform_scramble(sobol(index, 0), utils::hash_u32(seed ^ 0x5bd1e995)));
+
+        BlueNoiseSampler::shifted(value, self.shift(self.dimension))
+    }
+
+    fn get_2d(&mut self) -> (Float, Float) {
+        self.dimension += 1;
+        let seed = utils::hash_u32(self.seed ^ utils::hash_u32(self.dimension));
+        let index = nested_uniform_scramble(self.sample_index, seed);
+        let x = uint_to_float(nested_uniform_scramble(sobol(index, 0), utils::hash_u32(seed ^ 0x5bd1e995)));
+        let y = uint_to_float(nested_uniform_scramble(sobol(index, 1), utils::hash_u32(seed ^ 0x68e31da4)));
+
+        (
+            BlueNoiseSampler::shifted(x, self.shift(self.dimension)),
+            BlueNoiseSampler::shifted(y, self.shift(self.dimension ^ 0x8000_0000))
+        )
+    }
+}
+
+// Void-and-cluster (Ulichney 1993): returns a size x size tile of values in [0, 1) where
+// each threshold level forms a blue noise pattern
+fn generate_blue_noise(size: usize) -> Vec<Float> {
+    let num_pixels = size * size;
+    let sigma = 1.5;
+
+    // Toroidal gaussian energy kernel, indexed by offset
+    let mut kernel = vec![0.0; num_pixels];
+    for dy in 0..size {
+        for dx in 0..size {
+            let x = usize::min(dx, size - dx) as Float;
+            let y = usize::min(dy, size - dy) as Float;
+            kernel[dy * size + dx] = Float::exp(-(x * x + y * y) / (2.0 * sigma * sigma));
+        }
+    }
+
+    let toggle = |pattern: &mut Vec<bool>, energy: &mut Vec<Float>, index: usize| {
+        pattern[index] = !pattern[index];
+        let sign = if pattern[index] { 1.0 } else { -1.0 };
+        let (px, py) = (index % size, index / size);
+        for y in 0..size {
+            for x in 0..size {
+                let offset = ((y + size - py) % size) * size + ((x + size - px) % size);
+                energy[y * size + x] += sign * kernel[offset];
+            }
+        }
+    };
+
+    // Tightest cluster is the set pixel with the most energy, largest void the unset one with the least
+    let tightest_cluster = |pattern: &Vec<bool>, energy: &Vec<Float>| -> usize {
+        (0..num_pixels).filter(|&i| pattern[i]).fold(usize::MAX, |best, i| {
+            if best == usize::MAX || energy[i] > energy[best] { i } else { best }
+        })
+    };
+    let largest_void = |pattern: &Vec<bool>, energy: &Vec<Float>| -> usize {
+        (0..num_pixels).filter(|&i| !pattern[i]).fold(usize::MAX, |best, i| {
+            if best == usize::MAX || energy[i] < energy[best] { i } else { best }
+        })
+    };
+
+    // Initial random pattern, about a tenth of the pixels
+    let mut pattern = vec![false; num_pixels];
+    let mut energy = vec![0.0; num_pixels];
+    for i in 0..(num_pixels / 10) {
+        let index = (utils::hash_u32(i as u32 ^ 0x9e3779b9) as usize) % num_pixels;
+        if !pattern[index] {
+            toggle(&mut pattern, &mut energy, index);
+        }
+    }
+
+    // Move points from clusters into voids until the pattern settles
+    loop {
+        let cluster = tightest_cluster(&pattern, &energy);
+        toggle(&mut pattern, &mut energy, cluster);
+        let void = largest_void(&pattern, &energy);
+        toggle(&mut pattern, &mut energy, void);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let num_ones = pattern.iter().filter(|&&set| set).count();
+    let mut rank = vec![0; num_pixels];
+
+    // Rank the initial points by taking away the tightest clusters first
+    {
+        let mut pattern = pattern.clone();
+        let mut energy = energy.clone();
+        for r in (0..num_ones).rev() {
+            let cluster = tightest_cluster(&pattern, &energy);
+            toggle(&mut pattern, &mut energy, cluster);
+            rank[cluster] = r;
+        }
+    }
+
+    // Then rank the rest by filling the largest voids
+    for r in num_ones..num_pixels {
+        let void = largest_void(&pattern, &energy);
+        toggle(&mut pattern, &mut energy, void);
+        rank[void] = r;
+    }
+
+    rank.iter().map(|&r| ((r as Float) + 0.5) / (num_pixels as Float)).collect()
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Helpers
+
+const ONE_MINUS_EPSILON: Float = 1.0 - Float::EPSILON;
+
+fn pixel_seed(seed: u32, pixel_x: u32, pixel_y: u32) -> u32 {
+    utils::hash_combine(&[seed, pixel_x, pixel_y])
+}
+
+// Generator for the purely random parts of a sample, one stream per global seed
+fn sample_rng(seed: u32, pixel_x: u32, pixel_y: u32, sample_index: u32) -> utils::Rng {
+    let pixel = utils::hash_combine(&[pixel_x, pixel_y]) as u64;
+    utils::Rng::new_stream((pixel << 32) | (sample_index as u64), seed as u64)
+}
+
+// Top 24 bits to a float in [0, 1)
+fn uint_to_float(value: u32) -> Float {
+    ((value >> 8) as Float) * (1.0 / 16777216.0)
+}
+
+// Element i of a random permutation of [0, length), without storing the permutation (Kensler 2013)
+fn permute(index: u32, length: u32, seed: u32) -> u32 {
+    let mut w = length.wrapping_sub(1);
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    let mut i = index;
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < length {
+            break;
+        }
+    }
+
+    (i.wrapping_add(seed)) % length
+}
+
+// First two dimensions of the Sobol sequence. Dimension 0 is the van der Corput sequence,
+// dimension 1 uses the direction numbers of the primitive polynomial x + 1.
+fn sobol(index: u32, dimension: u32) -> u32 {
+    let mut result = 0;
+    let mut direction: u32 = 1 << 31;
+    let mut index = index;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= direction;
+        }
+
+        direction = if dimension == 0 { direction >> 1 } else { direction ^ (direction >> 1) };
+        index >>= 1;
+    }
+
+    result
+}
+
+fn laine_karras_permutation(value: u32, seed: u32) -> u32 {
+    let mut x = value.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+
+    x
+}
+
+fn nested_uniform_scramble(value: u32, seed: u32) -> u32 {
+    laine_karras_permutation(value.reverse_bits(), seed).reverse_bits()
+}
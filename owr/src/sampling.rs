@@ -1,10 +1,15 @@
-use crate::hittable::{Hittable};
 use crate::ray::{Ray};
 use crate::vec3::Vec3;
 use crate::types::*;
 use crate::color;
 use crate::camera;
-use crate::utils;
+use crate::aov::{AovType, AovSample, AovBuffers};
+use crate::film::Film;
+use crate::hittable::HitRecord;
+use crate::material::RayKind;
+use crate::scene::World;
+use crate::sampler::Sampler;
+use crate::light::{Light, power_heuristic};
 
 use rayon::prelude::*;
 use std::sync::Arc;
@@ -15,161 +20,485 @@ use indicatif::{ParallelProgressIterator, ProgressIterator, ProgressBar};
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn shoot_ray(r : &Ray<Float>, background: &Vec3<Float>, world: &Arc<dyn Hittable>, depth: u32) -> Vec3<Float> {
-    if depth <= 0 {
-        return Vec3::<Float>::default();
+// Adds light found at the given path vertex (0 is the first hit) to the result and the matching aov
+fn add_light(radiance: &mut Vec3<Float>, aov: &mut Option<&mut AovSample>, vertex: u32, light: Vec3<Float>) {
+    *radiance = *radiance + light;
+    if let Some(aov) = aov.as_mut() {
+        match vertex {
+            0 => { aov.emission = aov.emission + light; }
+            1 => { aov.direct = aov.direct + light; }
+            _ => { aov.indirect = aov.indirect + light; }
+        }
     }
+}
 
-    match world.hit(&r, 0.001, Float::MAX) {
-        // Hit something
-        Some(hit) => {
-            match hit.material.scatter(&r, &hit) {
-                Some(scatter_result) => {
-                    let emitted = hit.material.emitted(hit.u, hit.v, &hit.point);
-                    return emitted + shoot_ray(&scatter_result.scattered, &background, world, depth-1) * scatter_result.attenuation;
-                }
-                _ => {
-                    return hit.material.emitted(hit.u, hit.v, &hit.point);
+// Closest hit before t_max that rays of this kind can see, going through surfaces that are hidden from them
+fn visible_hit(world: &World, ray: &Ray<Float>, kind: RayKind, t_max: Float) -> Option<HitRecord> {
+    let mut t_min = 0.001;
+    loop {
+        let hit = world.objects.hit(ray, t_min, t_max)?;
+        if hit.material.visible(kind) {
+            return Some(hit);
+        }
+        t_min = hit.t + 0.001;
+    }
+}
+
+// Light reaching the hit point straight from a light (zero when something is in the way), times the bsdf and cosine.
+// For lights that scattered rays can find too (the environment) both ways are weighted with multiple importance sampling;
+// point, spot and directional lights can only be found this way.
+fn direct_light(light: &dyn Light, can_be_hit: bool, ray: &Ray<Float>, hit: &HitRecord, world: &World, u: (Float, Float)) -> Vec3<Float> {
+    let light_sample = match light.sample(&hit.point, u) {
+        Some(light_sample) if light_sample.pdf > 0.0 => { light_sample }
+        _ => { return Vec3::default(); }
+    };
+
+    let f = hit.material.eval(ray, hit, &light_sample.direction);
+    if f[0] <= 0.0 && f[1] <= 0.0 && f[2] <= 0.0 {
+        return Vec3::default();
+    }
+
+    // Shadow rays stand in for a diffuse bounce, so they go through lights hidden from those
+    let shadow_ray = Ray { orig: hit.point, dir: light_sample.direction, time: ray.time, wavelength: ray.wavelength };
+    let t_max = Float::min(light_sample.distance - 0.001, Float::MAX);
+    if visible_hit(world, &shadow_ray, RayKind::Diffuse, t_max).is_some() {
+        return Vec3::default();
+    }
+
+    let weight = if can_be_hit && !light_sample.is_delta {
+        power_heuristic(light_sample.pdf, hit.material.scattering_pdf(ray, hit, &light_sample.direction))
+    } else {
+        1.0
+    };
+
+    light_sample.radiance * f * (weight / light_sample.pdf)
+}
+
+// Follows a path from the camera. At every diffuse-like bounce the scene's lights get a shadow ray each, and so does
+// the environment when next event estimation is enabled.
+// When aovs are passed, the first hit is recorded and the light is split by path length: emission seen directly,
+// light reaching the first hit in one more step (direct), and everything else (indirect).
+fn trace_path(r: &Ray<Float>, params: &RaytracerParams, world: &World, sampler: &mut dyn Sampler, aov: Option<&mut AovSample>) -> Vec3<Float> {
+    let mut aov = aov;
+    let mut radiance = Vec3::<Float>::default();
+    let mut throughput = Vec3::<Float>::new(1.0, 1.0, 1.0);
+    let mut ray = *r;
+    let mut scattering_pdf = 0.0;
+    let mut kind = RayKind::Camera;
+
+    let environment = if params.next_event_estimation { world.background.as_light() } else { Option::None };
+
+    for bounce in 0..params.max_depth {
+        let hit = match visible_hit(world, &ray, kind, Float::MAX) {
+            Some(hit) => { hit }
+            _ => {
+                // The environment was sampled directly at the last bounce as well, unless that bounce was specular
+                let background = world.background.value(&ray.dir);
+                let weight = match environment {
+                    Some(environment) if scattering_pdf > 0.0 => { power_heuristic(scattering_pdf, environment.pdf(&ray.orig, &ray.dir)) }
+                    _ => { 1.0 }
+                };
+
+                if bounce == 0 {
+                    if let Some(aov) = aov.as_mut() {
+                        **aov = AovSample::miss(&background);
+                    }
+                    radiance = background;
+                } else {
+                    add_light(&mut radiance, &mut aov, bounce, background * throughput * weight);
                 }
+                break;
+            }
+        };
+
+        add_light(&mut radiance, &mut aov, bounce, hit.material.emitted(&hit) * throughput);
+
+        if bounce == 0 {
+            if let Some(aov) = aov.as_mut() {
+                aov.depth = hit.t * r.dir.length();
+                aov.normal = if hit.front_facing { hit.normal } else { hit.normal.reverse_dir() };
+                aov.uv = (hit.u, hit.v);
+                aov.object_id = hit.object_id;
+                aov.material_id = AovSample::material_id_from_ptr(Arc::as_ptr(&hit.material));
+            }
+        }
+
+        let scatter_result = hit.material.scatter(&ray, &hit, sampler);
+
+        // Shadow rays towards the lights and the environment, whichever lobe (if any) the scattered ray came from
+        if hit.material.has_non_specular_lobe() && bounce + 1 < params.max_depth {
+            for light in world.lights.iter() {
+                let direct = direct_light(light.as_ref(), false, &ray, &hit, world, sampler.get_2d());
+                add_light(&mut radiance, &mut aov, bounce + 1, direct * throughput);
+            }
+
+            if let Some(environment) = environment {
+                let direct = direct_light(environment, true, &ray, &hit, world, sampler.get_2d());
+                add_light(&mut radiance, &mut aov, bounce + 1, direct * throughput);
             }
         }
-        // No hits
-        _ => {
-            return *background;
+
+        let scatter_result = match scatter_result {
+            Some(scatter_result) => { scatter_result }
+            _ => { break; }
+        };
+
+        if bounce == 0 {
+            if let Some(aov) = aov.as_mut() {
+                aov.albedo = scatter_result.attenuation;
+            }
         }
+
+        scattering_pdf = hit.material.scattering_pdf(&ray, &hit, &scatter_result.scattered.dir);
+        kind = if scattering_pdf > 0.0 { RayKind::Diffuse } else { RayKind::Specular };
+
+        throughput = throughput * scatter_result.attenuation;
+        ray = scatter_result.scattered;
     }
+
+    radiance
 }
 
-// --------------------------------------------------------------------------------------------------------------------
+pub fn shoot_ray(r : &Ray<Float>, params: &RaytracerParams, world: &World, sampler: &mut dyn Sampler) -> Vec3<Float> {
+    trace_path(r, params, world, sampler, Option::None)
+}
+
+pub fn shoot_ray_with_aovs(r : &Ray<Float>, params: &RaytracerParams, world: &World, sampler: &mut dyn Sampler) -> (Vec3<Float>, AovSample) {
+    let mut aov = AovSample::default();
+    let radiance = trace_path(r, params, world, sampler, Some(&mut aov));
 
-pub fn one_sample(image_x: u32, image_y: u32, params: &RaytracerParams, camera: &camera::Camera, world: &Arc<dyn Hittable>) -> Vec3<Float> {
-    let u = ((image_x as Float) + utils::random_range(0.0, 1.0)) / ((params.image_width - 1) as Float);
-    let v = ((image_y as Float) + utils::random_range(0.0, 1.0)) / ((params.image_height - 1) as Float);
-    let r = camera.get_ray(u, v);
-    
-    shoot_ray(&r, &camera.get_background(), &world, params.max_depth)
+    (radiance, aov)
 }
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn multi_sample(enable_average_sum: bool, image_x: u32, image_y: u32, params: &RaytracerParams, camera: &camera::Camera, world: &Arc<dyn Hittable>) -> Vec3<Float> {
-    let mut sample_sum = Vec3::default();
-    for _s in 0..params.samples_per_pixel {
-        sample_sum = sample_sum + one_sample(image_x, image_y, &params, &camera, &world);
+// Number of image rows rendered together as one unit of (parallel) work
+const TILE_ROWS: u32 = 8;
+
+// --------------------------------------------------------------------------------------------------------------------
+
+// Shoots a ray through a continuous image position, where pixel (x, y) covers [x, x+1) x [y, y+1). Black where the
+// camera has no ray.
+pub fn one_sample_at(film_x: Float, film_y: Float, params: &RaytracerParams, camera: &dyn camera::Camera, world: &World, sampler: &mut dyn Sampler) -> Vec3<Float> {
+    let u = film_x / ((params.image_width - 1) as Float);
+    let v = film_y / ((params.image_height - 1) as Float);
+    match camera.get_ray(u, v, sampler) {
+        Some(r) => { shoot_ray(&r, params, world, sampler) }
+        _ => { Vec3::default() }
     }
+}
 
-    // Average out with num samples
-    if enable_average_sum {
-        return sample_sum * (1.0 / params.samples_per_pixel as Float);
-    } else {
-        return sample_sum;
+// The sampler is expected to be started on this pixel already, its first 2D sample picks the position in the pixel
+pub fn one_sample(image_x: u32, image_y: u32, params: &RaytracerParams, camera: &dyn camera::Camera, world: &World, sampler: &mut dyn Sampler) -> Vec3<Float> {
+    let (jitter_x, jitter_y) = sampler.get_2d();
+    let film_x = (image_x as Float) + jitter_x;
+    let film_y = (image_y as Float) + jitter_y;
+
+    one_sample_at(film_x, film_y, params, camera, world, sampler)
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+
+pub fn one_sample_with_aovs(film_x: Float, film_y: Float, params: &RaytracerParams, camera: &dyn camera::Camera, world: &World, sampler: &mut dyn Sampler) -> (Vec3<Float>, AovSample) {
+    let u = film_x / ((params.image_width - 1) as Float);
+    let v = film_y / ((params.image_height - 1) as Float);
+    match camera.get_ray(u, v, sampler) {
+        Some(r) => { shoot_ray_with_aovs(&r, params, world, sampler) }
+        _ => { (Vec3::default(), AovSample::miss(&Vec3::default())) }
     }
 }
 
 // --------------------------------------------------------------------------------------------------------------------
 
-fn get_grid(x0: u32, y0: u32, width: u32, height: u32) -> Vec::<(u32, u32)> {
-    // How many pixels?
-    let num_pixels = (width * height) as usize;
-
-    // Create a grid so we can divide the work
-    let x1 = x0 + width;
-    let y1 = y0 + height;
-    let mut grid = Vec::<(u32, u32)>::with_capacity(num_pixels);
-    for y in y0..y1 {
-        for x in x0..x1 {
-            grid.push((x,y));
+// Takes samples_per_pixel samples in every pixel of the rect (x0, y0, width, height) and splats them into the film
+// through the pixel filter. When aovs are requested, they are gathered per pixel (unfiltered) and pushed row by row.
+fn sample_rect(film: &mut Film, aovs: Option<&mut Vec<AovSample>>, rect: (u32, u32, u32, u32), params: &RaytracerParams, camera: &dyn camera::Camera, world: &World) {
+    let (x0, y0, width, height) = rect;
+    let mut aovs = aovs;
+    let mut sampler = params.sampler.create(params.samples_per_pixel, params.seed);
+    let sampler = sampler.as_mut();
+
+    for y in y0..(y0 + height) {
+        for x in x0..(x0 + width) {
+            let mut aov_sum = AovSample::default();
+            for s in 0..params.samples_per_pixel {
+                sampler.start_sample(x, y, s);
+                let (jitter_x, jitter_y) = sampler.get_2d();
+                let film_x = (x as Float) + jitter_x;
+                let film_y = (y as Float) + jitter_y;
+
+                let sample = if aovs.is_some() {
+                    let (sample, aov) = one_sample_with_aovs(film_x, film_y, params, camera, world, sampler);
+                    if s == 0 {
+                        aov_sum = aov;
+                    } else {
+                        aov_sum.accumulate(&aov);
+                    }
+
+                    sample
+                } else {
+                    one_sample_at(film_x, film_y, params, camera, world, sampler)
+                };
+
+                film.add_sample(&params.filter, film_x, film_y, &sample);
+            }
+
+            if let Some(aovs) = aovs.as_mut() {
+                aov_sum.scale(1.0 / params.samples_per_pixel as Float);
+                aovs.push(aov_sum);
+            }
         }
     }
+}
 
-    grid
+// Renders a region of the image. Pixels around the region are sampled as well (as far as the filter
+// reaches), so regions rendered independently, e.g. on different web workers, line up without seams.
+pub fn multisample_film_region(x0: u32, y0: u32, width: u32, height: u32, params: &RaytracerParams, camera: &dyn camera::Camera, world: &World) -> Film {
+    let margin = params.filter.pixel_margin();
+    let sample_x0 = x0.saturating_sub(margin);
+    let sample_y0 = y0.saturating_sub(margin);
+    let sample_x1 = u32::min(x0 + width + margin, params.image_width);
+    let sample_y1 = u32::min(y0 + height + margin, params.image_height);
+
+    let mut film = Film::new(x0, y0, width, height);
+    sample_rect(&mut film, None, (sample_x0, sample_y0, sample_x1 - sample_x0, sample_y1 - sample_y0), params, camera, world);
+
+    film
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+
+pub fn multi_sample(enable_average_sum: bool, image_x: u32, image_y: u32, params: &RaytracerParams, camera: &dyn camera::Camera, world: &World) -> Vec3<Float> {
+    let film = multisample_film_region(image_x, image_y, 1, 1, params, camera, world);
+    let average = film.pixel(image_x, image_y);
+
+    // Callers that do their own averaging expect a sum over samples_per_pixel samples
+    if enable_average_sum {
+        return average;
+    } else {
+        return average * (params.samples_per_pixel as Float);
+    }
 }
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn multisample_image_region(x0: u32, y0: u32, width: u32, height: u32, params: &RaytracerParams, camera: &camera::Camera, world: &Arc<dyn Hittable>) -> Vec<u8> {
-    let grid = get_grid(x0, y0, width, height);
+pub fn multisample_image_region(x0: u32, y0: u32, width: u32, height: u32, params: &RaytracerParams, camera: &dyn camera::Camera, world: &World) -> Vec<u8> {
+    let film = multisample_film_region(x0, y0, width, height, params, camera, world);
 
-    return grid.iter().flat_map(|&point| -> Color {
-        color::vec3_to_color(&multi_sample(true, point.0, point.1, &params, &camera, &world), 1.0)
+    return film.pixels().iter().flat_map(|pixel| -> Color {
+        color::vec3_to_color(pixel, 1.0)
     }).collect();
 }
 
 // --------------------------------------------------------------------------------------------------------------------
 
-fn multisample_image(enable_progress_bar: bool, params: &RaytracerParams, camera: &camera::Camera, world: &Arc<dyn Hittable>) -> Vec<u8> {
-    let grid = get_grid(0, 0, params.image_width, params.image_height);
+// Renders the whole image in tiles of rows. Each tile only samples its own rows but splats into a film that also
+// covers the rows the filter reaches into, the tiles are then added together.
+fn multisample_film(enable_parallel: bool, enable_progress_bar: bool, enable_aovs: bool, params: &RaytracerParams, camera: &dyn camera::Camera, world: &World) -> (Film, Vec<AovSample>) {
+    let tiles: Vec<u32> = (0..params.image_height).step_by(TILE_ROWS as usize).collect();
+    let margin = params.filter.pixel_margin();
+
+    let render_tile = |&tile_y: &u32| -> (Film, Vec<AovSample>) {
+        let tile_height = u32::min(TILE_ROWS, params.image_height - tile_y);
+        let film_y0 = tile_y.saturating_sub(margin);
+        let film_y1 = u32::min(tile_y + tile_height + margin, params.image_height);
+
+        let mut film = Film::new(0, film_y0, params.image_width, film_y1 - film_y0);
+        let mut aovs = Vec::new();
+        let tile_aovs = if enable_aovs { Some(&mut aovs) } else { None };
+        sample_rect(&mut film, tile_aovs, (0, tile_y, params.image_width, tile_height), params, camera, world);
+
+        (film, aovs)
+    };
+
+    let stitch_tiles = |tiles: Vec<(Film, Vec<AovSample>)>| -> (Film, Vec<AovSample>) {
+        let mut film = Film::new(0, 0, params.image_width, params.image_height);
+        let mut aovs = Vec::new();
+        for (tile_film, tile_aovs) in tiles {
+            film.add_film(&tile_film);
+            aovs.extend(tile_aovs);
+        }
+
+        (film, aovs)
+    };
 
     if enable_progress_bar {
         #[cfg(feature = "progress-ui")]
         {
-            let pb = ProgressBar::new(grid.len() as u64);
-            pb.set_draw_delta(64);
+            let pb = ProgressBar::new(tiles.len() as u64);
 
-            return grid.iter().progress_with(pb).flat_map(|&point| -> Color {
-                color::vec3_to_color(&multi_sample(true, point.0, point.1, &params, &camera, &world), 1.0)
-            }).collect()
+            if enable_parallel {
+                return stitch_tiles(tiles.par_iter().progress_with(pb).map(render_tile).collect());
+            } else {
+                return stitch_tiles(tiles.iter().progress_with(pb).map(render_tile).collect());
+            }
         }
     }
 
-    return grid.iter().flat_map(|&point| -> Color {
-        color::vec3_to_color(&multi_sample(true, point.0, point.1, &params, &camera, &world), 1.0)
-    }).collect();
+    if enable_parallel {
+        stitch_tiles(tiles.par_iter().map(render_tile).collect())
+    } else {
+        stitch_tiles(tiles.iter().map(render_tile).collect())
+    }
 }
 
 // --------------------------------------------------------------------------------------------------------------------
 
-fn multisample_image_parallel(enable_progress_bar: bool, params: &RaytracerParams, camera: &camera::Camera, world: &Arc<dyn Hittable>) -> Vec<u8> {
-    let grid = get_grid(0, 0, params.image_width, params.image_height);
+pub fn multi_sample_buffer(enable_average_sum: bool, enable_parallel: bool, params: &RaytracerParams, camera: &dyn camera::Camera, world: &World) -> Vec::<Float> {
+    let (film, _) = multisample_film(enable_parallel, false, false, params, camera, world);
 
-    if enable_progress_bar {
-        #[cfg(feature = "progress-ui")]
-        {
-            let pb = ProgressBar::new(grid.len() as u64);
-            pb.set_draw_delta(64);
+    // Callers that do their own averaging expect a sum over samples_per_pixel samples
+    let scale = if enable_average_sum { 1.0 } else { params.samples_per_pixel as Float };
 
-            return grid.par_iter().progress_with(pb).flat_map(|&point| -> Color {
-                color::vec3_to_color(&multi_sample(true, point.0, point.1, &params, &camera, &world), 1.0)
-            }).collect()
-        }
-    }
+    return film.pixels().iter()
+        .flat_map(|pixel| -> [Float; 4] {
+            [pixel[0] * scale, pixel[1] * scale, pixel[2] * scale, 1.0]
+        }).collect();
+}
+
+// --------------------------------------------------------------------------------------------------------------------
 
-    return grid.par_iter().flat_map(|&point| -> Color {
-        color::vec3_to_color(&multi_sample(true, point.0, point.1, &params, &camera, &world), 1.0)
+pub fn render_image(enable_parallel: bool, enable_progress_bar: bool, params: &RaytracerParams, camera: &dyn camera::Camera, world: &World) -> Option<image::RgbaImage> {
+    // Iterate and collect results
+    let (film, _) = multisample_film(enable_parallel, enable_progress_bar, false, params, camera, world);
+    let results = film.pixels().iter().flat_map(|pixel| -> Color {
+        color::vec3_to_color(pixel, 1.0)
     }).collect();
+
+    image::RgbaImage::from_raw(params.image_width, params.image_height, results)
 }
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn multi_sample_buffer(enable_average_sum: bool, enable_parallel: bool, params: &RaytracerParams, camera: &camera::Camera, world: &Arc<dyn Hittable>) -> Vec::<Float> {
-    let grid = get_grid(0, 0, params.image_width, params.image_height);
+pub fn render_aovs(enable_parallel: bool, enable_progress_bar: bool, params: &RaytracerParams, camera: &dyn camera::Camera, world: &World, aov_types: &[AovType]) -> AovBuffers {
+    let (film, aovs) = multisample_film(enable_parallel, enable_progress_bar, true, params, camera, world);
 
-    // Iterate and collect results
-    if enable_parallel {
-        return grid.par_iter()
-            .flat_map(|&point| -> [Float; 4] {
-                let &result = multi_sample(enable_average_sum, point.0, point.1, &params, &camera, &world).array();
-                [result[0], result[1], result[2], 1.0]
-            }).collect();
-    } else {
-        return grid.iter()
-            .flat_map(|&point| -> [Float; 4] {
-                let &result = multi_sample(enable_average_sum, point.0, point.1, &params, &camera, &world).array();
-                [result[0], result[1], result[2], 1.0]
-            }).collect();
+    let mut buffers = AovBuffers::new(params.image_width, params.image_height, aov_types);
+    for (index, (beauty, aov)) in film.pixels().iter().zip(aovs.iter()).enumerate() {
+        buffers.set_pixel(index, beauty, aov);
     }
+
+    buffers
 }
 
 // --------------------------------------------------------------------------------------------------------------------
 
-pub fn render_image(enable_parallel: bool, enable_progress_bar: bool, params: &RaytracerParams, camera: &camera::Camera, world: &Arc<dyn Hittable>) -> Option<image::RgbaImage> {
-    // Iterate and collect results
-    let results;
-    if enable_parallel {
-        results = multisample_image_parallel(enable_progress_bar, &params, &camera, &world);
-    } else {
-        results = multisample_image(enable_progress_bar, &params, &camera, &world);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::EnvironmentLight;
+    use crate::filter::PixelFilter;
+    use crate::background::ConstantBackground;
+    use crate::light::PointLight;
+    use crate::material::{Coated, Dielectric, Lambertian, Material, Metal, Principled};
+    use crate::sampler::SamplerType;
+    use crate::sphere::Sphere;
+    use crate::texture::SolidColor;
+
+    // Average light coming back off a sphere under an environment with a bright patch, the sphere filling the view
+    fn furnace(material: Arc<dyn Material>, next_event_estimation: bool) -> Float {
+        let mut pixels = vec![Vec3::new(0.5, 0.5, 0.5); 16 * 8];
+        pixels[3 * 16] = Vec3::new(100.0, 100.0, 100.0);
+        pixels[3 * 16 + 15] = Vec3::new(100.0, 100.0, 100.0);
+        let world = World {
+            objects: Arc::new(Sphere { center: Vec3::default(), radius: 1.0, material }),
+            background: Arc::new(EnvironmentLight::new(16, 8, pixels, 0.0, 1.0)),
+            lights: Vec::new()
+        };
+        let params = RaytracerParams {
+            aspect_ratio: 1.0,
+            image_width: 1,
+            image_height: 1,
+            samples_per_pixel: 1,
+            max_depth: 8,
+            filter: PixelFilter::default(),
+            sampler: SamplerType::Independent,
+            seed: 7,
+            next_event_estimation
+        };
+
+        let n = 200000;
+        let mut sampler = params.sampler.create(params.samples_per_pixel, params.seed);
+        let mut sum = 0.0f64;
+        for i in 0..n {
+            sampler.start_sample(0, 0, i);
+            let (x, y) = sampler.get_2d();
+            let target = Vec3::new(1.2 * x - 0.6, 1.2 * y - 0.6, 0.0);
+            let origin = Vec3::new(0.0, 0.0, 3.0);
+            let ray = Ray { orig: origin, dir: target - origin, time: 0.0, wavelength: Option::None };
+            sum += crate::light::luminance(&shoot_ray(&ray, &params, &world, sampler.as_mut())) as f64;
+        }
+
+        (sum / (n as f64)) as Float
     }
 
-    image::RgbaImage::from_raw(params.image_width, params.image_height, results)
+    // Sampling the environment directly has to give the same result as only following scattered rays
+    fn assert_light_sampling_keeps_energy(base: Arc<dyn Material>) {
+        let mut coated = Coated::new(base);
+        coated.roughness = Arc::new(SolidColor::new_scalar(0.5));
+        let coated: Arc<dyn Material> = Arc::new(coated);
+
+        let without = furnace(coated.clone(), false);
+        let with = furnace(coated, true);
+        assert!((with - without).abs() < 0.03 * without, "{} with light sampling, {} without", with, without);
+    }
+
+    #[test]
+    fn coat_over_specular_base_keeps_its_energy_with_light_sampling() {
+        assert_light_sampling_keeps_energy(Arc::new(Metal { albedo: Vec3::new(1.0, 1.0, 1.0), fuzz: 0.0 }));
+        assert_light_sampling_keeps_energy(Arc::new(Dielectric::new(1.5)));
+    }
+
+    #[test]
+    fn coat_over_diffuse_base_keeps_its_energy_with_light_sampling() {
+        assert_light_sampling_keeps_energy(Arc::new(Lambertian::new(Arc::new(SolidColor::new(&Vec3::new(0.8, 0.8, 0.8))))));
+    }
+
+    // A point light can only be found by light sampling, so the light reaching the first hit has to be the light
+    // sampled there every time, also when the ray went on through the refraction lobe
+    #[test]
+    fn transmissive_principled_is_lit_by_point_lights() {
+        let mut principled = Principled::new_from_color(&Vec3::new(0.8, 0.8, 0.8));
+        principled.transmission = Arc::new(SolidColor::new_scalar(1.0));
+        let material: Arc<dyn Material> = Arc::new(principled);
+        let light: Arc<dyn Light> = Arc::new(PointLight::new(&Vec3::new(2.0, 2.0, 3.0), &Vec3::new(10.0, 10.0, 10.0)));
+        let world = World {
+            objects: Arc::new(Sphere { center: Vec3::default(), radius: 1.0, material: material.clone() }),
+            background: Arc::new(ConstantBackground::new(&Vec3::default())),
+            lights: vec![light.clone()]
+        };
+        let params = RaytracerParams {
+            aspect_ratio: 1.0,
+            image_width: 1,
+            image_height: 1,
+            samples_per_pixel: 1,
+            max_depth: 8,
+            filter: PixelFilter::default(),
+            sampler: SamplerType::Independent,
+            seed: 7,
+            next_event_estimation: true
+        };
+
+        let n = 50000;
+        let mut sampler = params.sampler.create(params.samples_per_pixel, params.seed);
+        let mut direct = 0.0f64;
+        let mut expected = 0.0f64;
+        for i in 0..n {
+            sampler.start_sample(0, 0, i);
+            let (x, y) = sampler.get_2d();
+            let target = Vec3::new(1.2 * x - 0.6, 1.2 * y - 0.6, 0.0);
+            let origin = Vec3::new(0.0, 0.0, 3.0);
+            let ray = Ray { orig: origin, dir: target - origin, time: 0.0, wavelength: Option::None };
+
+            let hit = world.objects.hit(&ray, 0.001, Float::MAX).unwrap();
+            let light_sample = light.sample(&hit.point, (0.0, 0.0)).unwrap();
+            let f = material.eval(&ray, &hit, &light_sample.direction);
+            expected += crate::light::luminance(&(light_sample.radiance * f * (1.0 / light_sample.pdf))) as f64;
+
+            let (_, aov) = shoot_ray_with_aovs(&ray, &params, &world, sampler.as_mut());
+            direct += crate::light::luminance(&aov.direct) as f64;
+        }
+
+        assert!((direct - expected).abs() < 0.03 * expected, "{} direct, {} expected", direct / (n as f64), expected / (n as f64));
+    }
 }
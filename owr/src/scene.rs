@@ -0,0 +1,50 @@
+use crate::types::*;
+use crate::hittable::{Hittable, HittableList};
+use crate::background::Background;
+use crate::light::Light;
+use crate::bvh;
+
+use std::sync::Arc;
+
+// --------------------------------------------------------------------------------------------------------------------
+// Scene: the objects, what surrounds them, and lights that aren't objects (point, spot, directional)
+
+pub struct Scene {
+    pub objects: HittableList,
+    pub background: Arc<dyn Background>,
+    pub lights: Vec<Arc<dyn Light>>
+}
+
+impl Scene {
+    pub fn new(objects: HittableList, background: Arc<dyn Background>) -> Self {
+        Self {
+            objects,
+            background,
+            lights: Vec::new()
+        }
+    }
+
+    // Everything the renderer needs, with the objects optionally put in a bvh first
+    pub fn build_world(&self, enable_bvh: bool, time0: Float, time1: Float) -> World {
+        let objects: Arc<dyn Hittable> = if enable_bvh {
+            bvh::BvhNode::build_bvh(&self.objects, time0, time1)
+        } else {
+            Arc::new(HittableList { list: self.objects.list.clone() })
+        };
+
+        World {
+            objects,
+            background: self.background.clone(),
+            lights: self.lights.clone()
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// World: a scene ready for rendering
+
+pub struct World {
+    pub objects: Arc<dyn Hittable>,
+    pub background: Arc<dyn Background>,
+    pub lights: Vec<Arc<dyn Light>>
+}
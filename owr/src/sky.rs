@@ -0,0 +1,340 @@
+use crate::types::*;
+use crate::vec3::Vec3;
+use crate::utils;
+use crate::color;
+use crate::light::{self, Light, LightSample};
+use crate::background::Background;
+use crate::environment::EnvironmentLight;
+
+// --------------------------------------------------------------------------------------------------------------------
+// Physical sky: the Preetham et al. 1999 analytic daylight model plus a sun disc of the real angular size.
+//
+// Directions follow the environment map: +y is up, azimuth is measured from -z (north) towards +x (east).
+// The model works in kcd/m^2, so the sun is about a million times brighter than the sky around it, which
+// is why it's sampled as a light. SKY_UNITS brings a sunlit white wall to roughly 1 in the output.
+
+const SKY_UNITS: Float = 1.0 / 40.0;
+
+// Angular radius of the sun seen from the earth, and its luminance above the atmosphere (kcd/m^2)
+const SUN_ANGULAR_RADIUS: Float = 0.00465;
+const SUN_LUMINANCE: Float = 1.6e6;
+
+// Resolution of the lat-long table used to importance sample the sky dome
+const TABLE_WIDTH: usize = 256;
+const TABLE_HEIGHT: usize = 128;
+
+pub struct PhysicalSky {
+    sun_direction: Vec3<Float>,
+    turbidity: Float,
+    intensity: Float,
+    zenith: [Float; 3],
+    perez: [[Float; 5]; 3],
+    sun_radiance: Vec3<Float>,
+    sun_cos_max: Float,
+    sun_solid_angle: Float,
+    ground: Vec3<Float>,
+    sun_probability: Float,
+    table: EnvironmentLight
+}
+
+impl PhysicalSky {
+    // Sun elevation and azimuth in degrees, turbidity from 2 (very clear) to 10 (hazy); the ground below the
+    // horizon is a diffuse surface with the given albedo lit by the sky and sun
+    pub fn new(elevation: Float, azimuth: Float, turbidity: Float, intensity: Float, ground_albedo: &Vec3<Float>) -> Self {
+        let pi = std::f32::consts::PI as Float;
+        let turbidity = turbidity.clamp(1.7, 10.0);
+        let sun_direction = sun_direction(elevation, azimuth);
+
+        // The model is only defined for a sun above the horizon, keep the sky at twilight below that
+        let theta_s = Float::min(pi / 2.0 - elevation.to_radians(), pi / 2.0);
+        let t = turbidity;
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (pi - 2.0 * theta_s);
+        let zenith_luminance = Float::max((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192, 0.0);
+
+        let (t2, th, th2, th3) = (t * t, theta_s, theta_s * theta_s, theta_s * theta_s * theta_s);
+        let zenith_x =
+            t2 * (0.00166 * th3 - 0.00375 * th2 + 0.00209 * th) +
+            t * (-0.02903 * th3 + 0.06377 * th2 - 0.03202 * th + 0.00394) +
+            (0.11693 * th3 - 0.21196 * th2 + 0.06052 * th + 0.25886);
+        let zenith_y =
+            t2 * (0.00275 * th3 - 0.00610 * th2 + 0.00317 * th) +
+            t * (-0.04214 * th3 + 0.08970 * th2 - 0.04153 * th + 0.00516) +
+            (0.15346 * th3 - 0.26756 * th2 + 0.06670 * th + 0.26688);
+
+        let perez = [
+            [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+            [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+            [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529]
+        ];
+
+        // Zenith values divided by the Perez function at the zenith, so the sky is F(theta, gamma) times these
+        let zenith = [
+            zenith_luminance / perez_function(&perez[0], 1.0, theta_s),
+            zenith_x / perez_function(&perez[1], 1.0, theta_s),
+            zenith_y / perez_function(&perez[2], 1.0, theta_s)
+        ];
+
+        // Sun disc: the color is what gets through the air (rayleigh and aerosol scattering, Preetham appendix),
+        // a sun just below the horizon still lights the sky but isn't a light any more
+        let sun_cos_max = SUN_ANGULAR_RADIUS.cos();
+        let sun_solid_angle = 2.0 * pi * (1.0 - sun_cos_max);
+        let sun_radiance = if elevation > -SUN_ANGULAR_RADIUS.to_degrees() {
+            sun_transmittance(theta_s, turbidity) * SUN_LUMINANCE
+        } else {
+            Vec3::default()
+        };
+
+        let mut sky = Self {
+            sun_direction,
+            turbidity,
+            intensity,
+            zenith,
+            perez,
+            sun_radiance,
+            sun_cos_max,
+            sun_solid_angle,
+            ground: Vec3::default(),
+            sun_probability: 0.0,
+            table: EnvironmentLight::new(1, 1, vec![Vec3::default()], 0.0, 1.0)
+        };
+
+        // Light falling on the ground: the sun plus the upper half of the sky, cosine weighted
+        let mut sky_irradiance = Vec3::default();
+        let mut sky_power = 0.0;
+        let d_theta = pi / (TABLE_HEIGHT as Float);
+        let d_phi = 2.0 * pi / (TABLE_WIDTH as Float);
+        let mut pixels = Vec::with_capacity(TABLE_WIDTH * TABLE_HEIGHT);
+        for y in 0..TABLE_HEIGHT {
+            let theta = ((y as Float) + 0.5) * d_theta;
+            for x in 0..TABLE_WIDTH {
+                // Same mapping as EnvironmentLight, so each pixel is looked up where it was computed
+                let phi = (((x as Float) + 0.5) / (TABLE_WIDTH as Float) - 0.5) * 2.0 * pi;
+                let direction = Vec3::new(theta.sin() * phi.sin(), theta.cos(), -theta.sin() * phi.cos());
+                let radiance = sky.sky_radiance(&direction);
+                if direction.y() > 0.0 {
+                    let d_omega = theta.sin() * d_theta * d_phi;
+                    sky_irradiance = sky_irradiance + radiance * (direction.y() * d_omega);
+                    sky_power += light::luminance(&radiance) * d_omega;
+                }
+                pixels.push(radiance);
+            }
+        }
+
+        let sun_irradiance = sky.sun_radiance * (sun_solid_angle * Float::max(sun_direction.y(), 0.0));
+        sky.ground = *ground_albedo * (sky_irradiance + sun_irradiance) * (1.0 / pi);
+
+        // The table is sampled for the sky and ground, the disc by itself; pick between them by power
+        let ground_power = light::luminance(&sky.ground) * 2.0 * pi;
+        let sun_power = light::luminance(&sky.sun_radiance) * sun_solid_angle;
+        if sun_power > 0.0 {
+            sky.sun_probability = (sun_power / (sun_power + sky_power + ground_power)).clamp(0.1, 0.9);
+        }
+
+        for (y, row) in pixels.chunks_mut(TABLE_WIDTH).enumerate() {
+            if ((y as Float) + 0.5) * d_theta > pi / 2.0 {
+                row.iter_mut().for_each(|p| *p = sky.ground);
+            }
+        }
+        sky.table = EnvironmentLight::new(TABLE_WIDTH, TABLE_HEIGHT, pixels, 0.0, 1.0);
+
+        sky
+    }
+
+    pub fn sun_direction(&self) -> Vec3<Float> {
+        self.sun_direction
+    }
+
+    pub fn turbidity(&self) -> Float {
+        self.turbidity
+    }
+
+    // Sky dome without the sun disc, in model units
+    fn sky_radiance(&self, direction: &Vec3<Float>) -> Vec3<Float> {
+        let cos_theta = Float::max(direction.y(), 0.001);
+        let cos_gamma = direction.dot(&self.sun_direction).clamp(-1.0, 1.0);
+        let gamma = cos_gamma.acos();
+
+        let luminance = self.zenith[0] * perez_function(&self.perez[0], cos_theta, gamma);
+        let x = self.zenith[1] * perez_function(&self.perez[1], cos_theta, gamma);
+        let y = self.zenith[2] * perez_function(&self.perez[2], cos_theta, gamma);
+
+        xyy_to_rgb(x, y, luminance)
+    }
+
+    fn in_sun_disc(&self, direction: &Vec3<Float>) -> bool {
+        direction.dot(&self.sun_direction) >= self.sun_cos_max
+    }
+}
+
+impl Background for PhysicalSky {
+    fn value(&self, direction: &Vec3<Float>) -> Vec3<Float> {
+        let direction = direction.unit_vector();
+        if direction.y() < 0.0 {
+            return self.ground * (SKY_UNITS * self.intensity);
+        }
+
+        let mut radiance = self.sky_radiance(&direction);
+        if self.in_sun_disc(&direction) {
+            radiance = radiance + self.sun_radiance;
+        }
+
+        radiance * (SKY_UNITS * self.intensity)
+    }
+
+    fn as_light(&self) -> Option<&dyn Light> {
+        Some(self)
+    }
+}
+
+impl Light for PhysicalSky {
+    fn sample(&self, point: &Vec3<Float>, u: (Float, Float)) -> Option<LightSample> {
+        let direction = if u.0 < self.sun_probability {
+            let local = utils::cone_vec3_from((u.0 / self.sun_probability, u.1), self.sun_cos_max);
+            utils::local_to_world(&local, &self.sun_direction)
+        } else {
+            let u0 = (u.0 - self.sun_probability) / (1.0 - self.sun_probability);
+            self.table.sample(point, (Float::min(u0, 0.99999994), u.1))?.direction
+        };
+
+        let pdf = self.pdf(point, &direction);
+        if pdf == 0.0 {
+            return Option::None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance: Float::INFINITY,
+            radiance: self.value(&direction),
+            pdf,
+            is_delta: false
+        })
+    }
+
+    fn pdf(&self, point: &Vec3<Float>, direction: &Vec3<Float>) -> Float {
+        let direction = direction.unit_vector();
+        let sun_pdf = if self.in_sun_disc(&direction) { 1.0 / self.sun_solid_angle } else { 0.0 };
+
+        self.sun_probability * sun_pdf + (1.0 - self.sun_probability) * self.table.pdf(point, &direction)
+    }
+}
+
+unsafe impl Sync for PhysicalSky {}
+unsafe impl Send for PhysicalSky {}
+
+// --------------------------------------------------------------------------------------------------------------------
+
+// Unit vector towards the sun, angles in degrees
+pub fn sun_direction(elevation: Float, azimuth: Float) -> Vec3<Float> {
+    let (sin_e, cos_e) = elevation.to_radians().sin_cos();
+    let (sin_a, cos_a) = azimuth.to_radians().sin_cos();
+
+    Vec3::new(cos_e * sin_a, sin_e, -cos_e * cos_a)
+}
+
+// Sun elevation and azimuth (degrees, azimuth clockwise from north) for a place and local time
+// (NOAA general solar position equations, good to a fraction of a degree).
+// Latitude is positive north, longitude positive east, timezone in hours from UTC.
+pub fn sun_position(latitude: Float, longitude: Float, date: (i32, u32, u32), hour: Float, timezone: Float) -> (Float, Float) {
+    let (year, month, day) = date;
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days_before_month = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+    let month_index = (month.clamp(1, 12) - 1) as usize;
+    let day_of_year = days_before_month[month_index] + day + if leap && month > 2 { 1 } else { 0 };
+    let days_in_year = if leap { 366.0 } else { 365.0 };
+
+    let pi = std::f64::consts::PI;
+    let hour = hour as f64;
+    let gamma = 2.0 * pi / days_in_year * ((day_of_year as f64) - 1.0 + (hour - 12.0) / 24.0);
+
+    let equation_of_time = 229.18 * (0.000075 + 0.001868 * gamma.cos() - 0.032077 * gamma.sin()
+        - 0.014615 * (2.0 * gamma).cos() - 0.040849 * (2.0 * gamma).sin());
+    let declination = 0.006918 - 0.399912 * gamma.cos() + 0.070257 * gamma.sin()
+        - 0.006758 * (2.0 * gamma).cos() + 0.000907 * (2.0 * gamma).sin()
+        - 0.002697 * (3.0 * gamma).cos() + 0.00148 * (3.0 * gamma).sin();
+
+    // True solar time in minutes, and the hour angle (0 at solar noon)
+    let time_offset = equation_of_time + 4.0 * (longitude as f64) - 60.0 * (timezone as f64);
+    let solar_time = hour * 60.0 + time_offset;
+    let hour_angle = (solar_time / 4.0 - 180.0).to_radians();
+
+    let latitude = (latitude as f64).to_radians();
+    let cos_zenith = latitude.sin() * declination.sin() + latitude.cos() * declination.cos() * hour_angle.cos();
+    let elevation = 90.0 - cos_zenith.clamp(-1.0, 1.0).acos().to_degrees();
+
+    // Azimuth from south, turned around to be from north
+    let azimuth = f64::atan2(hour_angle.sin(), hour_angle.cos() * latitude.sin() - declination.tan() * latitude.cos()).to_degrees() + 180.0;
+
+    (elevation as Float, (azimuth % 360.0) as Float)
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+
+// Perez et al. sky distribution for a view at cos(theta) from the zenith, gamma radians from the sun
+fn perez_function(coefficients: &[Float; 5], cos_theta: Float, gamma: Float) -> Float {
+    let [a, b, c, d, e] = *coefficients;
+    let cos_gamma = gamma.cos();
+
+    (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+}
+
+// CIE xyY to linear sRGB, clipped to the gamut
+fn xyy_to_rgb(x: Float, y: Float, luminance: Float) -> Vec3<Float> {
+    if y <= 0.0 {
+        return Vec3::default();
+    }
+
+    let rgb = color::xyz_to_rgb(x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+    Vec3::new(Float::max(rgb[0], 0.0), Float::max(rgb[1], 0.0), Float::max(rgb[2], 0.0))
+}
+
+// Fraction of sunlight that makes it through the atmosphere, per channel (wavelengths 680, 550 and 440nm)
+fn sun_transmittance(theta_s: Float, turbidity: Float) -> Vec3<Float> {
+    let relative_mass = 1.0 / (theta_s.cos() + 0.15 * Float::powf(93.885 - theta_s.to_degrees(), -1.253));
+    let beta = 0.04608 * turbidity - 0.04586;
+    let alpha = 1.3;
+
+    let channel = |lambda: Float| {
+        let rayleigh = (-0.008735 * lambda.powf(-4.08) * relative_mass).exp();
+        let aerosol = (-beta * lambda.powf(-alpha) * relative_mass).exp();
+        rayleigh * aerosol
+    };
+
+    Vec3::new(channel(0.68), channel(0.55), channel(0.44))
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Number of table samples, out of a grid, within about 25 degrees of a direction
+    fn table_samples_near(sky: &PhysicalSky, direction: &Vec3<Float>) -> usize {
+        let n = 64;
+        let point = Vec3::default();
+        let mut count = 0;
+        for i in 0..n {
+            for j in 0..n {
+                let u = (((i as Float) + 0.5) / (n as Float), ((j as Float) + 0.5) / (n as Float));
+                if let Some(sample) = sky.table.sample(&point, u) {
+                    if sample.direction.dot(direction) > 0.9 {
+                        count += 1;
+                    }
+                }
+            }
+        }
+
+        count
+    }
+
+    #[test]
+    fn table_favors_the_side_of_the_sun() {
+        let sky = PhysicalSky::new(20.0, 90.0, 3.0, 1.0, &Vec3::new(0.2, 0.2, 0.2));
+        let sun = sky.sun_direction();
+        let near_sun = table_samples_near(&sky, &sun);
+
+        assert!(near_sun > table_samples_near(&sky, &sun.reverse_dir()));
+        assert!(near_sun > table_samples_near(&sky, &Vec3::new(-sun.x(), sun.y(), -sun.z())));
+    }
+}
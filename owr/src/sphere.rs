@@ -16,6 +16,12 @@ pub struct Sphere {
     pub material: Arc<dyn Material>
 }
 
+impl Sphere {
+    pub fn area(&self) -> Float {
+        4.0 * (std::f32::consts::PI as Float) * self.radius * self.radius
+    }
+}
+
 impl Hittable for Sphere {
     fn hit(&self, r: &Ray<Float>, t_min: Float, t_max: Float) -> Option<HitRecord> {
         let oc = r.orig - self.center;
@@ -23,6 +23,11 @@ impl SolidColor {
             color: *color
         }
     }
+
+    // The same value in every channel, for textures that drive a single number (roughness, metallic, ...)
+    pub fn new_scalar(value: Float) -> Self {
+        Self::new(&Vec3::new(value, value, value))
+    }
 }
 
 impl Texture for SolidColor {
@@ -65,6 +70,9 @@ impl Texture for Checker {
     }
 }
 
+unsafe impl Sync for Checker {}
+unsafe impl Send for Checker {}
+
 // --------------------------------------------------------------------------------------------------------------------
 
 pub struct Noise {
@@ -1,3 +1,6 @@
+use crate::filter::PixelFilter;
+use crate::sampler::SamplerType;
+
 // --------------------------------------------------------------------------------------------------------------------
 
 pub type Float = f32;
@@ -11,5 +14,9 @@ pub struct RaytracerParams {
     pub image_width: u32,
     pub image_height: u32,
     pub samples_per_pixel: u32,
-    pub max_depth: u32
+    pub max_depth: u32,
+    pub filter: PixelFilter,
+    pub sampler: SamplerType,
+    pub seed: u32,
+    pub next_event_estimation: bool
 }
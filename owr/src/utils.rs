@@ -6,39 +6,118 @@ use std::cell::RefCell;
 // --------------------------------------------------------------------------------------------------------------------
 
 thread_local! {
-    static NEXT_RAND: RefCell<u32> = RefCell::new(1);
-    static DETERMINISTIC_NEXT_RAND: RefCell<u32> = RefCell::new(1);
-}
-
-fn next_rand_u32(tls_int: &RefCell<u32>) -> u32 {
-    let mut next = tls_int.borrow_mut();
-    *next = *next * 1103515245 + 12345;
-
-    (*next/65536) % 32768
+    static NEXT_RAND: RefCell<Rng> = RefCell::new(Rng::new(1));
+    static DETERMINISTIC_NEXT_RAND: RefCell<Rng> = RefCell::new(Rng::new(1));
 }
 
 pub fn seed_rand(seed: u32) {
     NEXT_RAND.with(|next_rand| {
-        let mut next = next_rand.borrow_mut();
-        *next = seed;
+        *next_rand.borrow_mut() = Rng::new(seed);
     })
 }
 
 pub fn rand_u32() -> u32 {
-    // rand_value
     NEXT_RAND.with(|next_rand| {
-        return next_rand_u32(next_rand);
+        return next_rand.borrow_mut().next_u32();
     })
 }
 
+// Stream used to generate scenes; always starts from the same seed, so generated scenes look the same everywhere
 pub fn det_rand_u32() -> u32 {
     DETERMINISTIC_NEXT_RAND.with(|next_rand| {
-        return next_rand_u32(next_rand);
+        return next_rand.borrow_mut().next_u32();
     })
 }
 
 pub fn det_random_float() -> Float {
-    (det_rand_u32() as Float) / (32768.0)
+    DETERMINISTIC_NEXT_RAND.with(|next_rand| {
+        return next_rand.borrow_mut().next_float();
+    })
+}
+
+// Integer hash (lowbias32), turns ids and seeds into well distributed bits
+pub fn hash_u32(x: u32) -> u32 {
+    let mut x = x;
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846ca68b);
+    x ^= x >> 16;
+
+    x
+}
+
+// --------------------------------------------------------------------------------------------------------------------
+// Random number generator: PCG32 (XSH-RR variant, O'Neill 2014), 64 bits of state, 32 bits of output.
+//
+// The output is part of the interface: seeded scenes and renders depend on it, so the constants and the
+// seeding below must not change. For reference, new_stream(42, 54) starts with
+// 0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e (same as the pcg32 reference code).
+//
+// Generators with different streams give independent sequences even for the same seed, which is how work is
+// split between pixels and threads without sharing state.
+
+const PCG32_MULTIPLIER: u64 = 6364136223846793005;
+
+#[derive(Copy, Clone)]
+pub struct Rng {
+    state: u64,
+    increment: u64
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        Rng::new_stream(seed as u64, 0)
+    }
+
+    pub fn new_stream(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            increment: (stream << 1) | 1
+        };
+
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+
+        rng
+    }
+
+    // A new generator on a stream picked by this one, for handing to another thread or task
+    pub fn split(&mut self) -> Rng {
+        let seed = ((self.next_u32() as u64) << 32) | (self.next_u32() as u64);
+        let stream = ((self.next_u32() as u64) << 32) | (self.next_u32() as u64);
+
+        Rng::new_stream(seed, stream)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old_state = self.state;
+        self.state = old_state.wrapping_mul(PCG32_MULTIPLIER).wrapping_add(self.increment);
+
+        let xor_shifted = (((old_state >> 18) ^ old_state) >> 27) as u32;
+        let rotation = (old_state >> 59) as u32;
+
+        xor_shifted.rotate_right(rotation)
+    }
+
+    pub fn next_float(&mut self) -> Float {
+        float_from_u32(self.next_u32())
+    }
+
+    pub fn next_range(&mut self, min: Float, max: Float) -> Float {
+        min + (self.next_float() * (max - min))
+    }
+}
+
+// Float in [0, 1), using the top 24 bits so every value is exactly representable
+fn float_from_u32(x: u32) -> Float {
+    ((x >> 8) as Float) * (1.0 / 16777216.0)
+}
+
+// Combines several values into one seed
+pub fn hash_combine(values: &[u32]) -> u32 {
+    values.iter().fold(0x2545f491, |hash, &value| hash_u32(hash ^ value))
 }
 
 // --------------------------------------------------------------------------------------------------------------------
@@ -74,7 +153,9 @@ macro_rules! log_print {
 
 #[cfg(target_family = "wasm")]
 pub fn random_float() -> Float {
-    (rand_u32() as Float) / (32768.0)
+    NEXT_RAND.with(|next_rand| {
+        return next_rand.borrow_mut().next_float();
+    })
 }
 
 // --------------------------------------------------------------------------------------------------------------------
@@ -126,6 +207,73 @@ pub fn random_unit_vec3() -> Vec3<Float> {
     random_in_unit_sphere().unit_vector()
 }
 
+// --------------------------------------------------------------------------------------------------------------------
+// Warps from sampler values in [0, 1) (no rejection, so every call uses a fixed number of dimensions)
+
+// Concentric mapping of the square onto the unit disk (Shirley & Chiu)
+pub fn unitdisk_vec3_from(u: (Float, Float)) -> Vec3<Float> {
+    let ox = 2.0 * u.0 - 1.0;
+    let oy = 2.0 * u.1 - 1.0;
+    if ox == 0.0 && oy == 0.0 {
+        return Vec3::default();
+    }
+
+    let quarter_pi = std::f32::consts::FRAC_PI_4 as Float;
+    let (r, theta) = if ox.abs() > oy.abs() {
+        (ox, quarter_pi * (oy / ox))
+    } else {
+        (oy, 2.0 * quarter_pi - quarter_pi * (ox / oy))
+    };
+
+    Vec3::<Float>::new(r * theta.cos(), r * theta.sin(), 0.0)
+}
+
+// Uniform direction on the unit sphere
+pub fn unit_vec3_from(u: (Float, Float)) -> Vec3<Float> {
+    let z = 1.0 - 2.0 * u.0;
+    let r = Float::max(0.0, 1.0 - z * z).sqrt();
+    let phi = 2.0 * (std::f32::consts::PI as Float) * u.1;
+
+    Vec3::<Float>::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+// Cosine distributed direction around +z (Malley's method), pdf is cos(theta) / pi
+pub fn cosine_hemisphere_from(u: (Float, Float)) -> Vec3<Float> {
+    let d = unitdisk_vec3_from(u);
+    let z = Float::max(0.0, 1.0 - d.x() * d.x() - d.y() * d.y()).sqrt();
+
+    Vec3::<Float>::new(d.x(), d.y(), z)
+}
+
+// Uniform point inside the unit sphere
+pub fn in_unit_sphere_from(u: (Float, Float), u_radius: Float) -> Vec3<Float> {
+    unit_vec3_from(u) * u_radius.cbrt()
+}
+
+// Uniform direction inside a cone around +z, pdf is 1 / (2 pi (1 - cos_theta_max))
+pub fn cone_vec3_from(u: (Float, Float), cos_theta_max: Float) -> Vec3<Float> {
+    let z = 1.0 - u.0 * (1.0 - cos_theta_max);
+    let r = Float::max(0.0, 1.0 - z * z).sqrt();
+    let phi = 2.0 * (std::f32::consts::PI as Float) * u.1;
+
+    Vec3::<Float>::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+// Two unit vectors that make an orthonormal basis with the unit vector n (Duff et al. 2017)
+pub fn orthonormal_basis(n: &Vec3<Float>) -> (Vec3<Float>, Vec3<Float>) {
+    let sign = if n.z() >= 0.0 { 1.0 } else { -1.0 };
+    let a = -1.0 / (sign + n.z());
+    let b = n.x() * n.y() * a;
+
+    (Vec3::new(1.0 + sign * n.x() * n.x() * a, sign * b, -sign * n.x()), Vec3::new(b, sign + n.y() * n.y() * a, -n.y()))
+}
+
+// Local direction (x, y, z) in the basis around n
+pub fn local_to_world(local: &Vec3<Float>, n: &Vec3<Float>) -> Vec3<Float> {
+    let (t, b) = orthonormal_basis(n);
+    t * local.x() + b * local.y() + *n * local.z()
+}
+
 // --------------------------------------------------------------------------------------------------------------------
 
 pub fn det_random_range(min: Float, max: Float) -> Float {
@@ -168,4 +316,41 @@ pub fn get_sphere_uv(p: &Vec3<Float>) -> (Float, Float) {
     let v = theta / pi;
 
     (u, v)
-}
\ No newline at end of file
+}
+// --------------------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pcg32_matches_reference_sequence() {
+        // First outputs of the pcg32 reference code (pcg32-demo) for seed 42 on stream 54
+        let mut rng = Rng::new_stream(42, 54);
+        let expected = [0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e];
+        for value in expected {
+            assert_eq!(rng.next_u32(), value);
+        }
+    }
+
+    #[test]
+    fn next_float_uses_top_24_bits() {
+        let mut rng = Rng::new_stream(42, 54);
+        assert_eq!(rng.next_float(), 0.6303102);
+
+        assert_eq!(float_from_u32(0), 0.0);
+        assert_eq!(float_from_u32(0xff), 0.0);
+        assert_eq!(float_from_u32(0x100), 1.0 / 16777216.0);
+        // The largest output stays below one
+        assert_eq!(float_from_u32(u32::MAX), 0.99999994);
+    }
+
+    #[test]
+    fn hash_u32_is_stable() {
+        assert_eq!(hash_u32(0), 0);
+        assert_eq!(hash_u32(1), 0x688990c0);
+        assert_eq!(hash_u32(42), 0x172733c2);
+        assert_eq!(hash_u32(0xdeadbeef), 0xe628c683);
+        assert_eq!(hash_combine(&[1, 2, 3]), 0xd77e363a);
+    }
+}
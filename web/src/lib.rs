@@ -4,12 +4,9 @@ use owr::examples::scene_select;
 use owr::log_print;
 use owr::types::*;
 use owr::camera;
-use owr::hittable;
-use owr::utils as owr_utils;
-use owr::bvh;
+use owr::scene;
 
 use wasm_bindgen::{prelude::*, Clamped};
-use std::sync::Arc;
 use std::collections::HashMap;
 use reqwest;
 use std::path::{Path, PathBuf};
@@ -105,8 +102,8 @@ impl ResourceCache {
 #[wasm_bindgen]
 pub struct WebRaytracer {
     params: RaytracerParams,
-    camera: camera::Camera,
-    world: Arc<dyn hittable::Hittable>,
+    camera: camera::ProjectiveCamera,
+    world: scene::World,
 }
 
 impl WebRaytracer {
@@ -115,7 +112,7 @@ impl WebRaytracer {
         Self {
             params: example_scene.0,
             camera: example_scene.1,
-            world: if enable_bvh { bvh::BvhNode::build_bvh(&example_scene.2, 0.0, 1.0) } else { Arc::new(example_scene.2) }
+            world: example_scene.2.build_world(enable_bvh, 0.0, 1.0)
         }
     }
 
@@ -205,9 +202,10 @@ pub fn create_webraytracer(resource_cache: &ResourceCache, scene_num: u32, image
     WebRaytracer::new(scene_num, image_width, image_height, samples_per_pixel, max_depth, enable_bvh, image)
 }
 
+// Global render seed; every worker has to use the same one for the regions to match up
 #[wasm_bindgen]
-pub fn seed_rand(seed: u32) {
-    owr_utils::seed_rand(seed)
+pub fn set_render_seed(raytracer: &mut WebRaytracer, seed: u32) {
+    raytracer.params.seed = seed;
 }
 
 #[wasm_bindgen]
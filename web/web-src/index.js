@@ -20,6 +20,9 @@ const threadsModeOutput = document.getElementById('threadsMode');
 const resourceMap = new Map();
 var previewImgData;
 
+// Seed for all the render randomness, the same as the console app's default so renders can be compared
+const renderSeed = 0;
+
 // --------------------------------------------------------------------------------------------------------------------
 
 function updateTimeLabel(timeInMs) {
@@ -58,7 +61,8 @@ function getRenderFunction(handler) {
             height,
             numSamples,
             maxDepth,
-            enableBvh
+            enableBvh,
+            seed: renderSeed
         });
 
         updateTimeLabel(time);
@@ -94,7 +98,7 @@ function getPreviewFunction() {
         const numSamples = parseInt(samplesNumOutput.value);
         const maxDepth = parseInt(maxDepthNumOutput.value);
         const enableBvh = (bvhEnableOutput.value === 'true');
-        let { rawImageData, time } = await ManualWorkerPool.workerPoolRenderImage({ sceneNum, previewCb, width, height, numSamples, maxDepth, enableBvh });
+        let { rawImageData, time } = await ManualWorkerPool.workerPoolRenderImage({ sceneNum, previewCb, width, height, numSamples, maxDepth, enableBvh, seed: renderSeed });
 
         // Done rendering
         updateTimeLabel(time);
@@ -77,7 +77,7 @@ async function scanLineWorkerFunc(previewCb, worker, scanLines, imageWidth, imag
     }
 }
 
-async function renderImageScanlines(previewCb, scanLines, sceneNum, imageWidth, imageHeight, samplesPerPixel, maxDepth, enableBvh) {
+async function renderImageScanlines(previewCb, scanLines, sceneNum, imageWidth, imageHeight, samplesPerPixel, maxDepth, enableBvh, seed) {
     // Create buffer to store final results
     var finalBufferSize = 0;
     var finalResults = null;
@@ -86,9 +86,9 @@ async function renderImageScanlines(previewCb, scanLines, sceneNum, imageWidth,
         finalResults = new Uint8ClampedArray(finalBufferSize);
     }
 
-    // Have our workers create the raytracer objects
+    // Have our workers create the raytracer objects, all with the same seed so the scan lines they render match up
     for (var workerId = 0; workerId < WorkerPool.length; workerId++) {
-        await WorkerPool[workerId].workerCreateRaytracer(sceneNum, imageWidth, imageHeight, samplesPerPixel, maxDepth, enableBvh);
+        await WorkerPool[workerId].workerCreateRaytracer(sceneNum, imageWidth, imageHeight, samplesPerPixel, maxDepth, enableBvh, seed);
     }
 
     // Kick off all the workers
@@ -107,11 +107,11 @@ async function renderImageScanlines(previewCb, scanLines, sceneNum, imageWidth,
 
 // --------------------------------------------------------------------------------------------------------------------
   
-async function workerPoolRenderImage({ sceneNum, previewCb, width, height, numSamples, maxDepth, enableBvh }) {
+async function workerPoolRenderImage({ sceneNum, previewCb, width, height, numSamples, maxDepth, enableBvh, seed = 0 }) {
     const start = performance.now();
     const maxScanLineHeight = 4;
     const scanLines = buildScanLines(width, height, maxScanLineHeight);
-    const rawImageData =  await renderImageScanlines(previewCb, scanLines, sceneNum, width, height, numSamples, maxDepth, enableBvh);
+    const rawImageData =  await renderImageScanlines(previewCb, scanLines, sceneNum, width, height, numSamples, maxDepth, enableBvh, seed);
     const time = performance.now() - start;
 
     if (previewCb != null) {
@@ -127,8 +127,8 @@ async function workerPoolRenderImage({ sceneNum, previewCb, width, height, numSa
     }
 }
 
-async function workerPoolRenderImageNoPreview({ sceneNum, width, height, numSamples, maxDepth, enableBvh }) {
-    return await workerPoolRenderImage({ sceneNum, previewCb: null, width, height, numSamples, maxDepth, enableBvh });
+async function workerPoolRenderImageNoPreview({ sceneNum, width, height, numSamples, maxDepth, enableBvh, seed = 0 }) {
+    return await workerPoolRenderImage({ sceneNum, previewCb: null, width, height, numSamples, maxDepth, enableBvh, seed });
 }
 
 // --------------------------------------------------------------------------------------------------------------------
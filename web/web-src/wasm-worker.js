@@ -15,11 +15,12 @@ function createResourceCache(importedHandler, resourceMap) {
 // --------------------------------------------------------------------------------------------------------------------
 
 function wrapRenderImageFunc(importedHandler, resourceCache) {
-    return ({ sceneNum, width, height, numSamples, maxDepth, enableBvh }) =>
+    return ({ sceneNum, width, height, numSamples, maxDepth, enableBvh, seed = 0 }) =>
     {
         // Render
         const start = performance.now();
         const raytracer = importedHandler.create_webraytracer(resourceCache, sceneNum, width, height, numSamples, maxDepth, enableBvh );
+        importedHandler.set_render_seed(raytracer, seed);
         const rawImageData = importedHandler.render_image(raytracer);
         const time = performance.now() - start;
         return {